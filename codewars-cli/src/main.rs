@@ -0,0 +1,590 @@
+mod output;
+
+use chrono::{Duration, Utc};
+use codewars_rs::badge::{Badge, Theme};
+use codewars_rs::codewars::{Codewars, Mode, DEFAULT_BASE_URL};
+use codewars_rs::compare::compare;
+use codewars_rs::err::Error;
+use codewars_rs::heatmap::Heatmap;
+use codewars_rs::hydrate::Hydrator;
+use codewars_rs::kata_ref::KataRef;
+use codewars_rs::overlap::{Overlap, SolvedKata};
+use codewars_rs::recommend::Recommender;
+use codewars_rs::scaffold::Scaffold;
+use codewars_rs::search::{Index, Query};
+use codewars_rs::stats::UserStats;
+use codewars_rs::store::Store;
+use codewars_rs::sync::sync_user;
+use codewars_rs::team::{Leaderboard, SortBy, Team};
+use codewars_rs::user::User;
+use output::{render, render_distribution, render_overlap, Format};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+use structopt::StructOpt;
+
+/// Command-line access to the Codewars REST API.
+#[derive(StructOpt)]
+#[structopt(name = "codewars")]
+struct Opt {
+    /// API token, sent in Authorization header
+    #[structopt(long, env = "CODEWARS_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Base URL of the API, e.g. of a local mock server
+    #[structopt(long, env = "CODEWARS_BASE_URL")]
+    base_url: Option<String>,
+
+    /// Directory of the persistent response cache
+    #[structopt(long, env = "CODEWARS_CACHE_DIR", parse(from_os_str))]
+    cache_dir: Option<PathBuf>,
+
+    /// Answer only from the cache, never use the network
+    #[structopt(long)]
+    offline: bool,
+
+    /// Output format
+    #[structopt(
+        long,
+        short,
+        default_value = "table",
+        possible_values = &["table", "json", "yaml", "csv"]
+    )]
+    format: Format,
+
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Shows a user profile
+    User { username: String },
+    /// Lists challenges completed by a user
+    Completed { username: String },
+    /// Lists challenges authored by a user
+    Authored { username: String },
+    /// Shows a code challenge by id, slug or URL
+    Kata { kata: String },
+    /// Shows statistics of challenges completed by a user
+    Stats { username: String },
+    /// Shows a calendar heatmap of challenges completed by a user
+    Heatmap {
+        username: String,
+        /// Prints an SVG image instead of drawing in the terminal
+        #[structopt(long)]
+        svg: bool,
+        /// Draws with shaded blocks instead of colors
+        #[structopt(long)]
+        plain: bool,
+        /// Number of weeks shown, ending today
+        #[structopt(long, default_value = "52", parse(try_from_str = parse_weeks))]
+        weeks: i64,
+    },
+    /// Prints an SVG profile card of a user
+    Badge {
+        username: String,
+        /// Color theme
+        #[structopt(long, default_value = "dark", possible_values = &["dark", "light"])]
+        theme: String,
+        /// Number of languages shown
+        #[structopt(long, default_value = "3")]
+        languages: usize,
+    },
+    /// Shows completed challenges of a user by rank, category and tag
+    Distribution { username: String },
+    /// Compares users side by side, marking leaders
+    Compare {
+        #[structopt(required = true, min_values = 2)]
+        usernames: Vec<String>,
+        /// Marks leaders with `*` instead of colors
+        #[structopt(long)]
+        plain: bool,
+    },
+    /// Shows katas users have all completed, katas only one of them completed and how
+    /// similar their histories are
+    Overlap {
+        #[structopt(required = true, min_values = 2)]
+        usernames: Vec<String>,
+        /// Lists katas the other users completed and this user hasn't, most popular first
+        #[structopt(long = "for")]
+        for_user: Option<String>,
+    },
+    /// Recommends katas to solve next in a language, drawn from katas in the local store
+    Recommend {
+        username: String,
+        /// Language to solve the katas in
+        #[structopt(long = "lang")]
+        language: String,
+        /// SQLite database with the user and known katas
+        #[structopt(long, env = "CODEWARS_STORE", parse(from_os_str))]
+        store: PathBuf,
+        /// Syncs the user's history into the store first
+        #[structopt(long)]
+        sync: bool,
+        /// How many ranks above the user's rank katas may be
+        #[structopt(long, default_value = "1")]
+        stretch: i64,
+        #[structopt(long, default_value = "10")]
+        limit: usize,
+        /// Also recommends katas completed only in other languages
+        #[structopt(long)]
+        other_languages: bool,
+    },
+    /// Searches katas in the local store, e.g. `tag:arrays rank:<=5kyu lang:rust "dynamic
+    /// programming"`
+    Search {
+        #[structopt(required = true)]
+        query: Vec<String>,
+        /// SQLite database with known katas
+        #[structopt(long, env = "CODEWARS_STORE", parse(from_os_str))]
+        store: PathBuf,
+        #[structopt(long, default_value = "20")]
+        limit: usize,
+    },
+    /// Shows a leaderboard of users listed in a file, one username per line
+    Team {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// Ranks by `honor`, `score` or score in a language, e.g. `rust`
+        #[structopt(long, default_value = "honor")]
+        by: String,
+        /// SQLite database with snapshots for week-over-week deltas; current members are
+        /// saved to it
+        #[structopt(long, env = "CODEWARS_STORE", parse(from_os_str))]
+        store: Option<PathBuf>,
+        /// Prints HTML instead of a Markdown table
+        #[structopt(long)]
+        html: bool,
+    },
+    /// Creates a local workspace for solving a code challenge
+    Init {
+        /// Id, slug or URL of the code challenge
+        kata: String,
+        /// Language of the solution skeleton, defaults to the language of a
+        /// `/train/<language>` URL
+        #[structopt(long)]
+        lang: Option<String>,
+        /// Directory to create, defaults to the slug
+        #[structopt(long, parse(from_os_str))]
+        dir: Option<PathBuf>,
+        /// Directory of templates overriding the built-in ones
+        #[structopt(long, parse(from_os_str))]
+        templates: Option<PathBuf>,
+    },
+}
+
+/// Failures of a command, with the exit code each one is reported with. Invalid
+/// arguments exit with `2`, like usage errors reported by the argument parser.
+#[derive(Debug)]
+enum CliError {
+    Codewars(Error),
+    Output(String),
+}
+
+impl From<Error> for CliError {
+    fn from(error: Error) -> Self {
+        CliError::Codewars(error)
+    }
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Output(_) => 1,
+            CliError::Codewars(error) => match error {
+                Error::InvalidKataRef { .. } | Error::InvalidQuery { .. } => 2,
+                Error::UserNotFound { .. } | Error::ChallengeNotFound { .. } => 3,
+                Error::ReqwestError { .. } => 4,
+                Error::CodewarsError { .. } => 5,
+                Error::NotCached { .. } => 6,
+                Error::JsonError { .. } => 7,
+                Error::LanguageNotSupported { .. } | Error::TemplateNotFound { .. } => 8,
+                Error::IoError { .. } | Error::StoreError { .. } => 9,
+                _ => 1,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Codewars(error) => write!(f, "{}", error),
+            CliError::Output(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Parses a number of weeks, which must be at least one.
+fn parse_weeks(weeks: &str) -> Result<i64, String> {
+    match weeks.parse::<i64>() {
+        Ok(weeks) if weeks >= 1 => Ok(weeks),
+        Ok(_) => Err("must be at least 1".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn client(opt: &Opt) -> Codewars {
+    let mut codewars = Codewars::with_base_url(
+        opt.token.clone().unwrap_or_default(),
+        opt.base_url
+            .clone()
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+    );
+    if let Some(cache_dir) = &opt.cache_dir {
+        codewars = codewars.with_disk_cache(cache_dir.clone());
+    }
+    if opt.offline {
+        codewars = codewars.with_mode(Mode::Offline);
+    }
+    codewars
+}
+
+fn run(opt: &Opt) -> Result<String, CliError> {
+    let codewars = client(opt);
+    let rendered = match &opt.command {
+        Command::User { username } => render(&codewars.get_user(username.clone())?, opt.format),
+        Command::Completed { username } => render(
+            &codewars.get_completed_challenges(username.clone())?,
+            opt.format,
+        ),
+        Command::Authored { username } => render(
+            &codewars.get_authored_challenges(username.clone())?,
+            opt.format,
+        ),
+        Command::Kata { kata } => render(&codewars.get_code_challenge(kata.clone())?, opt.format),
+        Command::Stats { username } => {
+            let stats = UserStats::new(&codewars.get_completed_challenges(username.clone())?);
+            match opt.format {
+                Format::Table => Ok(stats.to_table()),
+                format => render(&stats, format),
+            }
+        }
+        Command::Heatmap {
+            username,
+            svg,
+            plain,
+            weeks,
+        } => {
+            let completed = codewars.get_completed_challenges(username.clone())?;
+            let end = Utc::now().date_naive();
+            let heatmap = Heatmap::new(&completed)
+                .with_range(end - Duration::weeks(*weeks) + Duration::days(1), end);
+            if *svg {
+                Ok(heatmap.to_svg())
+            } else {
+                Ok(heatmap.to_terminal(!plain))
+            }
+        }
+        Command::Badge {
+            username,
+            theme,
+            languages,
+        } => {
+            let user = codewars.get_user(username.clone())?;
+            let theme = match theme.as_str() {
+                "light" => Theme::light(),
+                _ => Theme::dark(),
+            };
+            Ok(Badge::new()
+                .with_theme(theme)
+                .with_languages(*languages)
+                .to_svg(&user))
+        }
+        Command::Distribution { username } => {
+            let completed = codewars.get_completed_challenges(username.clone())?;
+            let hydrated = Hydrator::new(&codewars).hydrate(&completed);
+            for (id, error) in &hydrated.errors {
+                eprintln!("warning: no details of {}: {}", id, error);
+            }
+            let distribution = hydrated.distribution();
+            match opt.format {
+                Format::Table => Ok(render_distribution(&distribution)),
+                format => render(&distribution, format),
+            }
+        }
+        Command::Compare { usernames, plain } => {
+            let users = usernames
+                .iter()
+                .map(|username| codewars.get_user(username.clone()))
+                .collect::<Result<Vec<User>, Error>>()?;
+            let comparison = compare(&users);
+            match opt.format {
+                Format::Table => Ok(comparison.to_table(!plain)),
+                format => render(&comparison, format),
+            }
+        }
+        Command::Overlap {
+            usernames,
+            for_user,
+        } => {
+            let (overlap, errors) = Overlap::fetch(&codewars, usernames);
+            for (username, error) in &errors {
+                eprintln!("warning: {} left out: {}", username, error);
+            }
+            match for_user {
+                Some(username) => {
+                    let recommendations: Vec<SolvedKata> = overlap
+                        .recommendations(username)
+                        .ok_or_else(|| {
+                            CliError::Output(format!(
+                                "{} isn't one of the compared users",
+                                username
+                            ))
+                        })?
+                        .into_iter()
+                        .cloned()
+                        .collect();
+                    render(&recommendations, opt.format)
+                }
+                None => match opt.format {
+                    Format::Table => Ok(render_overlap(&overlap)),
+                    format => render(&overlap, format),
+                },
+            }
+        }
+        Command::Recommend {
+            username,
+            language,
+            store,
+            sync,
+            stretch,
+            limit,
+            other_languages,
+        } => {
+            let mut store = Store::open(store)?;
+            if *sync {
+                let report = sync_user(&codewars, &mut store, username)?;
+                for error in &report.errors {
+                    eprintln!("warning: {}", error);
+                }
+            }
+            let recommendations = Recommender::new()
+                .with_stretch(*stretch)
+                .with_limit(*limit)
+                .with_other_languages(*other_languages)
+                .recommend_from_store(&store, username, language)?;
+            render(&recommendations, opt.format)
+        }
+        Command::Search {
+            query,
+            store,
+            limit,
+        } => {
+            let query = Query::parse(&query.join(" "))?;
+            let index = Index::from_store(&Store::open(store)?)?;
+            render(&index.search(&query, *limit), opt.format)
+        }
+        Command::Team {
+            file,
+            by,
+            store,
+            html,
+        } => {
+            let team = Team::from_file(file)?;
+            let (users, errors) = team.fetch(&codewars);
+            for (username, error) in &errors {
+                eprintln!("warning: {} left out: {}", username, error);
+            }
+            let baselines = match store {
+                Some(path) => {
+                    let mut store = Store::open(path)?;
+                    let baselines = team.week_ago(&store, &Utc::now())?;
+                    team.save_snapshots(&mut store, &users)?;
+                    baselines
+                }
+                None => HashMap::new(),
+            };
+            let sort_by = match by.as_str() {
+                "honor" => SortBy::Honor,
+                "score" => SortBy::Score,
+                language => SortBy::Language(language.to_string()),
+            };
+            let leaderboard = Leaderboard::new(&team.name, &users, sort_by, &baselines);
+            match opt.format {
+                Format::Table if *html => Ok(leaderboard.to_html()),
+                Format::Table => Ok(leaderboard.to_markdown()),
+                format => render(&leaderboard, format),
+            }
+        }
+        Command::Init {
+            kata,
+            lang,
+            dir,
+            templates,
+        } => {
+            let kata = KataRef::parse(kata)?;
+            let lang = match lang.as_ref().or(kata.language.as_ref()) {
+                Some(lang) => lang,
+                None => {
+                    return Err(CliError::Output(
+                        "--lang is required unless the kata URL names a language".to_string(),
+                    ))
+                }
+            };
+            let mut scaffold = Scaffold::new();
+            if let Some(templates) = templates {
+                scaffold = scaffold.with_template_dir(templates)?;
+            }
+            let challenge = codewars.get_code_challenge_by_ref(&kata)?;
+            let dir = dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(&challenge.slug));
+            let created = scaffold.generate(&challenge, lang, &dir)?;
+            Ok(created
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<String>>()
+                .join("\n"))
+        }
+    };
+    rendered.map_err(|e| CliError::Output(e.to_string()))
+}
+
+fn main() {
+    let opt = match Opt::from_iter_safe(std::env::args_os()) {
+        Ok(opt) => opt,
+        // Help and version are printed to stdout and exit with 0
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            eprintln!("{}", e.message);
+            process::exit(2);
+        }
+    };
+    match run(&opt) {
+        // Ignore errors such as a closed pipe when output is piped to `head`
+        Ok(output) => {
+            let _ = writeln!(io::stdout(), "{}", output.trim_end());
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(e.exit_code());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{run, CliError, Command, Opt};
+    use crate::output::Format;
+    use codewars_mock::{MockServer, MockServerBuilder};
+    use codewars_rs::err::Error;
+    use std::path::Path;
+    use structopt::StructOpt;
+
+    fn mock() -> MockServer {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/data");
+        MockServerBuilder::from_dir(&data_dir)
+            .unwrap()
+            .start()
+            .unwrap()
+    }
+
+    /// Parses arguments given after the global options pointing at `server`.
+    fn opt(server: &MockServer, args: &[&str]) -> Opt {
+        let base_url = server.base_url();
+        let mut all = vec!["codewars", "--base-url", &base_url];
+        all.extend(args);
+        Opt::from_iter_safe(all).unwrap()
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        let opt = Opt::from_iter_safe(&["codewars", "-f", "csv", "kata", "valid-braces"]).unwrap();
+        assert_eq!(opt.format, Format::Csv);
+        assert!(matches!(opt.command, Command::Kata { ref kata } if kata == "valid-braces"));
+
+        let opt = Opt::from_iter_safe(&["codewars", "user", "some_user"]).unwrap();
+        assert_eq!(opt.format, Format::Table);
+        assert!(!opt.offline);
+
+        assert!(Opt::from_iter_safe(&["codewars", "--format", "xml", "user", "a"]).is_err());
+        assert!(Opt::from_iter_safe(&["codewars", "user"]).is_err());
+        assert!(Opt::from_iter_safe(&["codewars"]).is_err());
+        assert!(Opt::from_iter_safe(&["codewars", "heatmap", "a", "--weeks", "0"]).is_err());
+        assert!(Opt::from_iter_safe(&["codewars", "heatmap", "a", "--weeks", "-3"]).is_err());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let code = |error: Error| CliError::Codewars(error).exit_code();
+        assert_eq!(CliError::Output("broken".to_string()).exit_code(), 1);
+        assert_eq!(
+            code(Error::InvalidKataRef {
+                kata: "a/b".to_string(),
+                reason: "".to_string(),
+            }),
+            2
+        );
+        assert_eq!(
+            code(Error::InvalidQuery {
+                query: "rank:".to_string(),
+                reason: "".to_string(),
+            }),
+            2
+        );
+        assert_eq!(
+            code(Error::UserNotFound {
+                username: "nobody".to_string(),
+            }),
+            3
+        );
+        assert_eq!(
+            code(Error::CodewarsError {
+                message: "".to_string(),
+            }),
+            5
+        );
+        assert_eq!(
+            code(Error::NotCached {
+                url: "".to_string(),
+            }),
+            6
+        );
+        assert_eq!(
+            code(Error::TemplateNotFound {
+                language: "cobol".to_string(),
+            }),
+            8
+        );
+    }
+
+    #[test]
+    fn test_run_formats() {
+        let server = mock();
+
+        let table = run(&opt(&server, &["user", "some_user"])).unwrap();
+        assert!(table
+            .lines()
+            .any(|line| line.starts_with("username") && line.trim_end().ends_with("some_user")));
+
+        let json = run(&opt(&server, &["--format", "json", "user", "some_user"])).unwrap();
+        let user: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(user["username"], "some_user");
+
+        let csv = run(&opt(
+            &server,
+            &["--format", "csv", "completed", "some_user"],
+        ))
+        .unwrap();
+        assert!(csv.starts_with("id,"));
+        assert_eq!(csv.trim_end().lines().count(), 4);
+    }
+
+    #[test]
+    fn test_run_exit_codes() {
+        let server = mock();
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store.db");
+        let store = store.to_str().unwrap();
+        let exit_code = |args: &[&str]| run(&opt(&server, args)).unwrap_err().exit_code();
+
+        assert_eq!(exit_code(&["user", "nobody"]), 3);
+        assert_eq!(exit_code(&["kata", "missing"]), 3);
+        assert_eq!(exit_code(&["kata", "https://example.com/kata/x"]), 2);
+        assert_eq!(exit_code(&["search", "--store", store, "rank:hard"]), 2);
+    }
+}
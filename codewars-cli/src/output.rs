@@ -0,0 +1,414 @@
+use codewars_rs::code_challenges::CodeChallenge;
+use codewars_rs::compare::Comparison;
+use codewars_rs::hydrate::Distribution;
+use codewars_rs::overlap::{Overlap, SolvedKata};
+use codewars_rs::recommend::Recommendation;
+use codewars_rs::search::Hit;
+use codewars_rs::stats::UserStats;
+use codewars_rs::table;
+use codewars_rs::team::Leaderboard;
+use codewars_rs::user::User;
+use codewars_rs::user_challenges::{AuthoredChallenge, CompletedChallenge};
+use serde::Serialize;
+use std::error::Error;
+use std::str::FromStr;
+
+/// Output formats supported by all commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Yaml,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "yaml" => Ok(Format::Yaml),
+            "csv" => Ok(Format::Csv),
+            other => Err(format!("Unknown format {}", other)),
+        }
+    }
+}
+
+/// A single row of tabular output as column name and value pairs.
+pub type Record = Vec<(&'static str, String)>;
+
+/// Values that can be printed as a table or CSV.
+pub trait ToRecords {
+    /// Returns rows of the value. All rows have the same columns.
+    fn to_records(&self) -> Vec<Record>;
+
+    /// Returns true if the value is a single object, printed as a two-column table of
+    /// fields and values.
+    fn is_single(&self) -> bool {
+        false
+    }
+}
+
+impl ToRecords for User {
+    fn to_records(&self) -> Vec<Record> {
+        let mut languages: Vec<_> = self.ranks.languages.iter().collect();
+        languages.sort_by(|a, b| b.1.score.cmp(&a.1.score).then(a.0.cmp(b.0)));
+        let languages: Vec<String> = languages
+            .iter()
+            .map(|(language, rank)| format!("{} ({})", language, rank.name))
+            .collect();
+        vec![vec![
+            ("username", self.username.clone()),
+            ("name", self.name.clone()),
+            ("clan", self.clan.clone().unwrap_or_default()),
+            ("honor", optional(self.honor)),
+            ("leaderboard_position", optional(self.leaderboard_position)),
+            ("rank", self.ranks.overall.name.clone()),
+            ("score", self.ranks.overall.score.to_string()),
+            (
+                "total_completed",
+                self.code_challenges.total_completed.to_string(),
+            ),
+            (
+                "total_authored",
+                self.code_challenges.total_authored.to_string(),
+            ),
+            ("languages", languages.join(", ")),
+            ("skills", self.skills.join(", ")),
+        ]]
+    }
+
+    fn is_single(&self) -> bool {
+        true
+    }
+}
+
+impl ToRecords for Vec<CompletedChallenge> {
+    fn to_records(&self) -> Vec<Record> {
+        self.iter()
+            .map(|challenge| {
+                vec![
+                    ("id", challenge.id.clone()),
+                    ("name", challenge.name.clone()),
+                    ("slug", challenge.slug.clone()),
+                    ("completed_at", challenge.completed_at.clone()),
+                    ("languages", challenge.completed_languages.join(", ")),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl ToRecords for Vec<AuthoredChallenge> {
+    fn to_records(&self) -> Vec<Record> {
+        self.iter()
+            .map(|challenge| {
+                vec![
+                    ("id", challenge.id.clone()),
+                    ("name", challenge.name.clone()),
+                    ("rank", challenge.rank_name.clone()),
+                    ("languages", challenge.languages.join(", ")),
+                    ("tags", challenge.tags.join(", ")),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl ToRecords for CodeChallenge {
+    fn to_records(&self) -> Vec<Record> {
+        vec![vec![
+            ("id", self.id.clone()),
+            ("name", self.name.clone()),
+            ("slug", self.slug.clone()),
+            ("url", self.url.clone()),
+            ("rank", self.rank.name.clone()),
+            ("category", self.category.clone()),
+            ("created_by", self.created_by.username.clone()),
+            ("total_attempts", self.total_attempts.to_string()),
+            ("total_completed", self.total_completed.to_string()),
+            ("total_stars", self.total_stars.to_string()),
+            ("vote_score", self.vote_score.to_string()),
+            ("completion_rate", format!("{:.2}", self.completion_rate())),
+            ("star_ratio", format!("{:.3}", self.star_ratio())),
+            ("issue_load", self.issue_load().to_string()),
+            ("quality", format!("{:.2}", self.quality_score())),
+            ("languages", self.languages.join(", ")),
+            ("tags", self.tags.join(", ")),
+        ]]
+    }
+
+    fn is_single(&self) -> bool {
+        true
+    }
+}
+
+/// Rows are per language; other statistics are only in JSON and YAML.
+impl ToRecords for UserStats {
+    fn to_records(&self) -> Vec<Record> {
+        self.per_language
+            .iter()
+            .map(|(language, count)| {
+                vec![
+                    ("language", language.clone()),
+                    ("completions", count.to_string()),
+                    (
+                        "first_seen",
+                        self.first_seen
+                            .get(language)
+                            .map(|time| time.to_rfc3339())
+                            .unwrap_or_default(),
+                    ),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Rows are per rank; categories and tags are only in JSON, YAML and tables.
+impl ToRecords for Distribution {
+    fn to_records(&self) -> Vec<Record> {
+        self.by_rank
+            .iter()
+            .map(|rank_count| {
+                vec![
+                    ("rank", rank_count.rank.name.clone()),
+                    ("count", rank_count.count.to_string()),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Rows are per user and metric, with users without a value left out.
+impl ToRecords for Comparison {
+    fn to_records(&self) -> Vec<Record> {
+        let mut records = vec![];
+        for row in &self.rows {
+            for (n, value) in row.values.iter().enumerate() {
+                if let Some(value) = value {
+                    records.push(vec![
+                        ("username", self.usernames[n].clone()),
+                        ("metric", row.metric.to_string()),
+                        ("value", value.clone()),
+                        ("leader", row.leaders.contains(&n).to_string()),
+                    ]);
+                }
+            }
+        }
+        records
+    }
+}
+
+impl ToRecords for Vec<SolvedKata> {
+    fn to_records(&self) -> Vec<Record> {
+        self.iter()
+            .map(|kata| {
+                vec![
+                    ("id", kata.id.clone()),
+                    ("name", kata.name.clone()),
+                    ("solvers", kata.solvers.len().to_string()),
+                    ("solved_by", kata.solvers.join(",")),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Rows are katas completed by at least one user.
+impl ToRecords for Overlap {
+    fn to_records(&self) -> Vec<Record> {
+        self.katas
+            .values()
+            .cloned()
+            .collect::<Vec<SolvedKata>>()
+            .to_records()
+    }
+}
+
+impl ToRecords for Vec<Recommendation> {
+    fn to_records(&self) -> Vec<Record> {
+        self.iter()
+            .map(|recommendation| {
+                vec![
+                    ("score", format!("{:.2}", recommendation.score)),
+                    ("rank", recommendation.challenge.rank.name.clone()),
+                    ("name", recommendation.challenge.name.clone()),
+                    ("slug", recommendation.challenge.slug.clone()),
+                    ("why", recommendation.explain()),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl ToRecords for Vec<Hit<'_>> {
+    fn to_records(&self) -> Vec<Record> {
+        self.iter()
+            .map(|hit| {
+                vec![
+                    ("score", format!("{:.2}", hit.score)),
+                    ("rank", hit.challenge.rank.name.clone()),
+                    ("name", hit.challenge.name.clone()),
+                    ("slug", hit.challenge.slug.clone()),
+                    ("tags", hit.challenge.tags.join(", ")),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl ToRecords for Leaderboard {
+    fn to_records(&self) -> Vec<Record> {
+        self.entries
+            .iter()
+            .map(|entry| {
+                vec![
+                    ("position", entry.position.to_string()),
+                    ("username", entry.username.clone()),
+                    ("rank", entry.rank.clone()),
+                    ("value", entry.value.to_string()),
+                    ("delta", optional(entry.delta)),
+                    ("movement", optional(entry.movement)),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn optional(value: Option<i64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Renders a value in given format.
+pub fn render<T>(value: &T, format: Format) -> Result<String, Box<dyn Error>>
+where
+    T: Serialize + ToRecords,
+{
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(value)?),
+        Format::Yaml => Ok(serde_yaml::to_string(value)?),
+        Format::Csv => render_csv(&value.to_records()),
+        Format::Table if value.is_single() => Ok(render_fields(&value.to_records())),
+        Format::Table => Ok(render_table(&value.to_records())),
+    }
+}
+
+fn render_csv(records: &[Record]) -> Result<String, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    if let Some(first) = records.first() {
+        writer.write_record(first.iter().map(|(column, _)| *column))?;
+    }
+    for record in records {
+        writer.write_record(record.iter().map(|(_, value)| value))?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Renders a distribution as tables of ranks, categories and tags, most common tags
+/// first.
+pub fn render_distribution(distribution: &Distribution) -> String {
+    let counts = |column: &'static str, counts: Vec<(&String, &usize)>| -> Vec<Record> {
+        counts
+            .into_iter()
+            .map(|(key, count)| vec![(column, key.clone()), ("count", count.to_string())])
+            .collect()
+    };
+    let mut tags: Vec<(&String, &usize)> = distribution.by_tag.iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let mut sections = vec![
+        render_table(&distribution.to_records()),
+        render_table(&counts(
+            "category",
+            distribution.by_category.iter().collect(),
+        )),
+        render_table(&counts("tag", tags)),
+    ];
+    if distribution.unknown > 0 {
+        sections.push(format!(
+            "{} challenges without details",
+            distribution.unknown
+        ));
+    }
+    sections.join("\n\n")
+}
+
+/// Renders overlap as tables of common katas, katas only one user completed and
+/// similarity of every pair of users.
+pub fn render_overlap(overlap: &Overlap) -> String {
+    let katas = |katas: Vec<&SolvedKata>| -> Vec<Record> {
+        katas
+            .into_iter()
+            .map(|kata| vec![("id", kata.id.clone()), ("name", kata.name.clone())])
+            .collect()
+    };
+    let common = overlap.common();
+    let mut sections = vec![format!(
+        "Completed by everyone: {}\n{}",
+        common.len(),
+        render_table(&katas(common))
+    )];
+    for (username, unique) in overlap.unique() {
+        sections.push(format!(
+            "Only {}: {}\n{}",
+            username,
+            unique.len(),
+            render_table(&katas(unique))
+        ));
+    }
+    let similarities: Vec<Record> = overlap
+        .similarities()
+        .into_iter()
+        .map(|similarity| {
+            vec![
+                ("first", similarity.first),
+                ("second", similarity.second),
+                ("common", similarity.common.to_string()),
+                ("jaccard", format!("{:.2}", similarity.jaccard)),
+            ]
+        })
+        .collect();
+    if !similarities.is_empty() {
+        sections.push(render_table(&similarities));
+    }
+    sections
+        .iter()
+        .map(|section| section.trim_end().to_string())
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+/// Renders rows as a table with a header line and aligned columns.
+pub fn render_table(records: &[Record]) -> String {
+    let first = match records.first() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    let header: Vec<&str> = first.iter().map(|(column, _)| *column).collect();
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| record.iter().map(|(_, value)| value.clone()).collect())
+        .collect();
+    table::render(&header, &rows)
+}
+
+/// Renders a single row as aligned `field  value` lines.
+fn render_fields(records: &[Record]) -> String {
+    let record = match records.first() {
+        Some(record) => record,
+        None => return String::new(),
+    };
+    let width = record
+        .iter()
+        .map(|(column, _)| column.len())
+        .max()
+        .unwrap_or(0);
+    record
+        .iter()
+        .map(|(column, value)| format!("{:<width$}  {}", column, value, width = width))
+        .map(|line| line.trim_end().to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,507 @@
+//! A local mock of the Codewars REST API.
+//!
+//! The mock serves the same endpoints that `codewars-rs` calls, either from a
+//! directory of JSON files or from data supplied through [`MockServerBuilder`].
+//! Faults such as latency, rate limiting, server errors and malformed JSON can be
+//! injected per route so client behaviour can be tested without the network.
+//!
+//! Successful responses carry an `ETag` header, unless disabled, and requests with a
+//! matching `If-None-Match` header are answered with `304 Not Modified`.
+
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tiny_http::{Header, Request, Response, Server};
+
+/// Number of completed challenges returned per page by the real API.
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// A fault that can be injected into responses of the mock server.
+#[derive(Clone, Debug)]
+pub enum Fault {
+    /// Delays the response by given duration and then serves it normally.
+    Latency(Duration),
+    /// Responds with `429 Too Many Requests`.
+    TooManyRequests,
+    /// Responds with `500 Internal Server Error`.
+    InternalServerError,
+    /// Responds with `200 OK` and a body that is not valid JSON.
+    MalformedJson,
+}
+
+/// A fault together with the routes it applies to.
+#[derive(Clone, Debug)]
+struct FaultRule {
+    path_prefix: String,
+    fault: Fault,
+    remaining: Option<usize>,
+}
+
+/// Data served by the mock server.
+#[derive(Default)]
+struct MockData {
+    users: HashMap<String, Value>,
+    completed: HashMap<String, Vec<Value>>,
+    authored: HashMap<String, Vec<Value>>,
+    code_challenges: HashMap<String, Value>,
+}
+
+/// Shared state between the server thread and the [`MockServer`] handle.
+struct State {
+    data: MockData,
+    faults: Vec<FaultRule>,
+    page_size: usize,
+    etags: bool,
+    requests: Vec<String>,
+    statuses: Vec<u16>,
+}
+
+/// A builder that collects data and faults for a [`MockServer`].
+pub struct MockServerBuilder {
+    data: MockData,
+    faults: Vec<FaultRule>,
+    page_size: usize,
+    etags: bool,
+}
+
+impl MockServerBuilder {
+    /// Returns a new builder without any data.
+    pub fn new() -> Self {
+        MockServerBuilder {
+            data: MockData::default(),
+            faults: vec![],
+            page_size: DEFAULT_PAGE_SIZE,
+            etags: true,
+        }
+    }
+
+    /// Returns a builder filled up with data from a directory.
+    ///
+    /// The directory may contain following subdirectories, all of them optional:
+    /// - `users/<username>.json` with a user object
+    /// - `completed/<username>.json` with an array of completed challenges
+    /// - `authored/<username>.json` with an array of authored challenges
+    /// - `code-challenges/<anything>.json` with a code challenge object
+    pub fn from_dir(dir: &Path) -> io::Result<Self> {
+        let mut builder = Self::new();
+        for (_, user) in read_json_dir(&dir.join("users"))? {
+            builder = builder.user(user);
+        }
+        for (username, challenges) in read_json_dir(&dir.join("completed"))? {
+            builder = builder.completed_challenges(&username, into_array(challenges));
+        }
+        for (username, challenges) in read_json_dir(&dir.join("authored"))? {
+            builder = builder.authored_challenges(&username, into_array(challenges));
+        }
+        for (_, challenge) in read_json_dir(&dir.join("code-challenges"))? {
+            builder = builder.code_challenge(challenge);
+        }
+        Ok(builder)
+    }
+
+    /// Adds a user. The user is served under its `username` field.
+    pub fn user(mut self, user: Value) -> Self {
+        let username = string_field(&user, "username");
+        self.data.users.insert(username, user);
+        self
+    }
+
+    /// Sets completed challenges of a user. They are paginated like the real API.
+    pub fn completed_challenges(mut self, username: &str, challenges: Vec<Value>) -> Self {
+        self.data.completed.insert(username.to_string(), challenges);
+        self
+    }
+
+    /// Sets authored challenges of a user.
+    pub fn authored_challenges(mut self, username: &str, challenges: Vec<Value>) -> Self {
+        self.data.authored.insert(username.to_string(), challenges);
+        self
+    }
+
+    /// Adds a code challenge. The challenge is served under both its `id` and `slug`.
+    pub fn code_challenge(mut self, challenge: Value) -> Self {
+        for key in &["id", "slug"] {
+            let value = string_field(&challenge, key);
+            if !value.is_empty() {
+                self.data.code_challenges.insert(value, challenge.clone());
+            }
+        }
+        self
+    }
+
+    /// Sets number of completed challenges returned per page.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Sets whether responses carry an `ETag` header. Enabled by default.
+    pub fn etags(mut self, etags: bool) -> Self {
+        self.etags = etags;
+        self
+    }
+
+    /// Injects a fault into every request whose path starts with `path_prefix`.
+    ///
+    /// Paths are relative to the base URL, e.g. `/users/some_user`.
+    pub fn fault(mut self, path_prefix: &str, fault: Fault) -> Self {
+        self.faults.push(FaultRule {
+            path_prefix: path_prefix.to_string(),
+            fault,
+            remaining: None,
+        });
+        self
+    }
+
+    /// Injects a fault into the next `times` requests whose path starts with `path_prefix`.
+    pub fn fault_times(mut self, path_prefix: &str, fault: Fault, times: usize) -> Self {
+        self.faults.push(FaultRule {
+            path_prefix: path_prefix.to_string(),
+            fault,
+            remaining: Some(times),
+        });
+        self
+    }
+
+    /// Starts the server on a random local port.
+    pub fn start(self) -> io::Result<MockServer> {
+        self.start_on("127.0.0.1:0")
+    }
+
+    /// Starts the server on given address.
+    pub fn start_on(self, addr: &str) -> io::Result<MockServer> {
+        let server = Server::http(addr).map_err(io::Error::other)?;
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("Server is not listening on an IP address"))?;
+        let server = Arc::new(server);
+        let state = Arc::new(Mutex::new(State {
+            data: self.data,
+            faults: self.faults,
+            page_size: self.page_size,
+            etags: self.etags,
+            requests: vec![],
+            statuses: vec![],
+        }));
+
+        let thread_server = Arc::clone(&server);
+        let thread_state = Arc::clone(&state);
+        let handle = thread::spawn(move || {
+            for request in thread_server.incoming_requests() {
+                let state = Arc::clone(&thread_state);
+                thread::spawn(move || handle_request(&state, request));
+            }
+        });
+
+        Ok(MockServer {
+            server,
+            addr,
+            state,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Default for MockServerBuilder {
+    /// Returns a new builder without any data.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A running mock server. The server stops when this handle is dropped.
+pub struct MockServer {
+    server: Arc<Server>,
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Returns a new builder for the server.
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder::new()
+    }
+
+    /// Returns the address server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns a base URL that can be passed to the client instead of the Codewars one.
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api/v1", self.addr)
+    }
+
+    /// Returns paths (including query) of all requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Returns status codes of all responses sent so far, oldest first.
+    pub fn statuses(&self) -> Vec<u16> {
+        self.state.lock().unwrap().statuses.clone()
+    }
+
+    /// Adds or replaces a user served by the running server.
+    pub fn set_user(&self, user: Value) {
+        let username = string_field(&user, "username");
+        self.state.lock().unwrap().data.users.insert(username, user);
+    }
+
+    /// Replaces completed challenges of a user served by the running server.
+    pub fn set_completed_challenges(&self, username: &str, challenges: Vec<Value>) {
+        self.state
+            .lock()
+            .unwrap()
+            .data
+            .completed
+            .insert(username.to_string(), challenges);
+    }
+
+    /// Replaces authored challenges of a user served by the running server.
+    pub fn set_authored_challenges(&self, username: &str, challenges: Vec<Value>) {
+        self.state
+            .lock()
+            .unwrap()
+            .data
+            .authored
+            .insert(username.to_string(), challenges);
+    }
+
+    /// Injects a fault into the running server. See [`MockServerBuilder::fault_times`].
+    pub fn add_fault(&self, path_prefix: &str, fault: Fault, times: Option<usize>) {
+        self.state.lock().unwrap().faults.push(FaultRule {
+            path_prefix: path_prefix.to_string(),
+            fault,
+            remaining: times,
+        });
+    }
+
+    /// Blocks current thread until the server stops.
+    pub fn wait(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Reads all `*.json` files in a directory and returns them keyed by file stem.
+fn read_json_dir(dir: &Path) -> io::Result<Vec<(String, Value)>> {
+    let mut files = vec![];
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let value = serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        files.push((stem, value));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+/// Returns the array inside a value, or the value itself wrapped in an array.
+fn into_array(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(values) => values,
+        other => vec![other],
+    }
+}
+
+fn string_field(value: &Value, field: &str) -> String {
+    value
+        .get(field)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Outcome of routing a single request.
+enum Reply {
+    Json(Value),
+    Status(u16, &'static str),
+    Malformed,
+}
+
+fn handle_request(state: &Mutex<State>, request: Request) {
+    let url = request.url().to_string();
+    let path = url
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("/api/v1")
+        .to_string();
+    let if_none_match = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("If-None-Match"))
+        .map(|header| header.value.to_string());
+
+    let (faults, reply, etags) = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(url.clone());
+        let faults = take_faults(&mut state.faults, &path);
+        let reply = route(&state, &path, &url);
+        (faults, reply, state.etags)
+    };
+
+    let mut reply = reply;
+    for fault in faults {
+        match fault {
+            Fault::Latency(delay) => thread::sleep(delay),
+            Fault::TooManyRequests => {
+                reply = Reply::Status(429, "Too Many Requests");
+                break;
+            }
+            Fault::InternalServerError => {
+                reply = Reply::Status(500, "Internal Server Error");
+                break;
+            }
+            Fault::MalformedJson => {
+                reply = Reply::Malformed;
+                break;
+            }
+        }
+    }
+
+    let json_header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = match reply {
+        Reply::Json(body) if !etags => {
+            Response::from_string(body.to_string()).with_header(json_header)
+        }
+        Reply::Json(body) => {
+            let body = body.to_string();
+            let etag = etag_for(&body);
+            let etag_header = Header::from_bytes("ETag", etag.as_str()).unwrap();
+            if if_none_match.as_deref() == Some(etag.as_str()) {
+                Response::from_string("")
+                    .with_status_code(304)
+                    .with_header(etag_header)
+            } else {
+                Response::from_string(body)
+                    .with_header(json_header)
+                    .with_header(etag_header)
+            }
+        }
+        Reply::Malformed => Response::from_string("{\"data\": [").with_header(json_header),
+        Reply::Status(code, reason) => {
+            Response::from_string(json!({ "success": false, "reason": reason }).to_string())
+                .with_status_code(code)
+                .with_header(json_header)
+        }
+    };
+    state
+        .lock()
+        .unwrap()
+        .statuses
+        .push(response.status_code().0);
+    let _ = request.respond(response);
+}
+
+/// Returns a quoted entity tag derived from a response body.
+fn etag_for(body: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Returns faults applicable to a path and consumes one use of each limited fault.
+fn take_faults(rules: &mut Vec<FaultRule>, path: &str) -> Vec<Fault> {
+    let mut faults = vec![];
+    for rule in rules.iter_mut() {
+        if !path.starts_with(&rule.path_prefix) {
+            continue;
+        }
+        match rule.remaining {
+            Some(0) => continue,
+            Some(ref mut remaining) => *remaining -= 1,
+            None => {}
+        }
+        faults.push(rule.fault.clone());
+    }
+    rules.retain(|rule| rule.remaining != Some(0));
+    faults
+}
+
+fn route(state: &State, path: &str, url: &str) -> Reply {
+    let not_found = Reply::Status(404, "Not Found");
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["users", username] => match state.data.users.get(*username) {
+            Some(user) => Reply::Json(user.clone()),
+            None => not_found,
+        },
+        ["users", username, "code-challenges", "completed"] => {
+            match state.data.completed.get(*username) {
+                Some(challenges) => {
+                    Reply::Json(completed_page(challenges, page_param(url), state.page_size))
+                }
+                None => not_found,
+            }
+        }
+        ["users", username, "code-challenges", "authored"] => {
+            match state.data.authored.get(*username) {
+                Some(challenges) => Reply::Json(json!({ "data": challenges })),
+                None => not_found,
+            }
+        }
+        ["code-challenges", challenge] => match state.data.code_challenges.get(*challenge) {
+            Some(challenge) => Reply::Json(challenge.clone()),
+            None => not_found,
+        },
+        _ => not_found,
+    }
+}
+
+fn page_param(url: &str) -> usize {
+    url.split('?')
+        .nth(1)
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.strip_prefix("page="))
+        .find_map(|page| page.parse().ok())
+        .unwrap_or(0)
+}
+
+fn completed_page(challenges: &[Value], page: usize, page_size: usize) -> Value {
+    let total_items = challenges.len();
+    let total_pages = total_items.div_ceil(page_size);
+    let data: Vec<&Value> = challenges
+        .iter()
+        .skip(page * page_size)
+        .take(page_size)
+        .collect();
+    json!({
+        "totalPages": total_pages,
+        "totalItems": total_items,
+        "data": data,
+    })
+}
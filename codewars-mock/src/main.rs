@@ -0,0 +1,49 @@
+use codewars_mock::MockServerBuilder;
+use std::env;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "Usage: codewars-mock <data-dir> [--addr 127.0.0.1:8080]";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut data_dir: Option<&str> = None;
+    let mut addr = "127.0.0.1:8080";
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--addr" if i + 1 < args.len() => {
+                addr = &args[i + 1];
+                i += 1;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            arg if data_dir.is_none() && !arg.starts_with('-') => data_dir = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+        i += 1;
+    }
+
+    let data_dir = match data_dir {
+        Some(dir) => dir,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let server = MockServerBuilder::from_dir(Path::new(data_dir))
+        .and_then(|builder| builder.start_on(addr))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to start mock server: {}", e);
+            process::exit(1);
+        });
+    println!("Serving Codewars mock API at {}", server.base_url());
+    server.wait();
+}
@@ -0,0 +1,254 @@
+//! SVG profile cards of users.
+//!
+//! A card shows username, clan, overall rank in its color, honor, leaderboard
+//! position and the best ranked languages. The output only depends on the user and
+//! options, so cards can be compared byte for byte.
+
+use crate::colors;
+use crate::escape::escape_html;
+use crate::user::{OverallRank, User};
+use std::fmt::Write;
+
+const WIDTH: i64 = 340;
+const LANGUAGES_TOP: i64 = 104;
+const LANGUAGE_STEP: i64 = 22;
+
+/// A structure that holds colors of a card.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub background: String,
+    pub border: String,
+    pub text: String,
+    pub muted: String,
+}
+
+impl Theme {
+    /// Returns a theme with light text on dark background, like Codewars.
+    pub fn dark() -> Self {
+        Theme {
+            background: "#1f2023".to_string(),
+            border: "#3a3b3f".to_string(),
+            text: "#e8e8e8".to_string(),
+            muted: "#9a9a9a".to_string(),
+        }
+    }
+
+    /// Returns a theme with dark text on light background.
+    pub fn light() -> Self {
+        Theme {
+            background: "#ffffff".to_string(),
+            border: "#d0d7de".to_string(),
+            text: "#24292f".to_string(),
+            muted: "#6e7781".to_string(),
+        }
+    }
+}
+
+impl Default for Theme {
+    /// Returns a new instance of Theme struct with default values.
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// Generates profile cards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    theme: Theme,
+    languages: usize,
+}
+
+impl Badge {
+    /// Returns a new instance of Badge struct with dark theme and three languages.
+    pub fn new() -> Self {
+        Badge {
+            theme: Theme::dark(),
+            languages: 3,
+        }
+    }
+
+    /// Sets colors of the card.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets how many languages are shown, best ranked first.
+    pub fn with_languages(mut self, languages: usize) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Returns the card of a user as a standalone SVG image.
+    pub fn to_svg(&self, user: &User) -> String {
+        let mut languages: Vec<(&String, &OverallRank)> = user.ranks.languages.iter().collect();
+        languages.sort_by(|a, b| b.1.score.cmp(&a.1.score).then(a.0.cmp(b.0)));
+        languages.truncate(self.languages);
+        let height = LANGUAGES_TOP + languages.len() as i64 * LANGUAGE_STEP + 4;
+        let theme = &self.theme;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" font-family=\"Segoe UI, Helvetica, Arial, sans-serif\">",
+            WIDTH, height
+        );
+        let _ = writeln!(
+            svg,
+            "<rect x=\"0.5\" y=\"0.5\" width=\"{}\" height=\"{}\" rx=\"6\" fill=\"{}\" stroke=\"{}\"/>",
+            WIDTH - 1,
+            height - 1,
+            theme.background,
+            theme.border
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"16\" y=\"30\" font-size=\"18\" font-weight=\"bold\" fill=\"{}\">{}</text>",
+            theme.text,
+            escape_html(&user.username)
+        );
+        if let Some(clan) = user.clan.as_ref().filter(|clan| !clan.is_empty()) {
+            let _ = writeln!(
+                svg,
+                "<text x=\"16\" y=\"50\" font-size=\"12\" fill=\"{}\">{}</text>",
+                theme.muted,
+                escape_html(clan)
+            );
+        }
+        rank_pill(
+            &mut svg,
+            &user.ranks.overall,
+            WIDTH - 16 - 64,
+            14,
+            64,
+            24,
+            13,
+        );
+
+        let position = user
+            .leaderboard_position
+            .map(|position| format!("#{}", position))
+            .unwrap_or_else(|| "-".to_string());
+        let honor = user
+            .honor
+            .map(|honor| honor.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        for (n, (label, value)) in [("Honor", honor), ("Leaderboard", position)]
+            .iter()
+            .enumerate()
+        {
+            let x = 16 + n as i64 * 110;
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"74\" font-size=\"11\" fill=\"{}\">{}</text>",
+                x, theme.muted, label
+            );
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"92\" font-size=\"15\" font-weight=\"bold\" fill=\"{}\">{}</text>",
+                x, theme.text, value
+            );
+        }
+
+        for (n, (language, rank)) in languages.iter().enumerate() {
+            let y = LANGUAGES_TOP + n as i64 * LANGUAGE_STEP;
+            rank_pill(&mut svg, rank, 16, y, 48, 18, 11);
+            let _ = writeln!(
+                svg,
+                "<text x=\"74\" y=\"{}\" font-size=\"13\" fill=\"{}\">{}</text>",
+                y + 13,
+                theme.text,
+                escape_html(language)
+            );
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"end\" fill=\"{}\">{}</text>",
+                WIDTH - 16,
+                y + 13,
+                theme.muted,
+                rank.score
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl Default for Badge {
+    /// Returns a new instance of Badge struct with default values.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draws a rank name on a rounded rectangle in the rank color.
+fn rank_pill(
+    svg: &mut String,
+    rank: &OverallRank,
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+    font_size: i64,
+) {
+    let fill = colors::rank_color(&rank.color).unwrap_or(colors::BLACK);
+    // Dark text on light colors
+    let text = match rank.color.as_str() {
+        "white" | "yellow" => "#1f2023",
+        _ => "#ffffff",
+    };
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\"/>",
+        x,
+        y,
+        width,
+        height,
+        height / 2,
+        fill
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-weight=\"bold\" text-anchor=\"middle\" \
+         fill=\"{}\">{}</text>",
+        x + width / 2,
+        y + height / 2 + font_size * 35 / 100,
+        font_size,
+        text,
+        escape_html(&rank.name)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Badge, Theme};
+    use crate::user::User;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_badge_snapshot() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let json = serde_json::from_str(
+            &fs::read_to_string(data_dir.join("users/some_user.json")).unwrap(),
+        )
+        .unwrap();
+        let mut user = User::new();
+        user.from_json(&json);
+        user.clan = Some("<some> & clan".to_string());
+
+        let dark = Badge::new().to_svg(&user);
+        let expected = fs::read_to_string(data_dir.join("badges/some_user.svg")).unwrap();
+        assert_eq!(dark, expected);
+
+        let light = Badge::new()
+            .with_theme(Theme::light())
+            .with_languages(1)
+            .to_svg(&user);
+        assert!(light.contains("fill=\"#ffffff\" stroke=\"#d0d7de\""));
+        assert_eq!(light.matches(">3 kyu</text>").count(), 2);
+        assert!(!light.contains(">ruby</text>"));
+    }
+}
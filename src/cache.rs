@@ -0,0 +1,187 @@
+use lru::LruCache;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+/// Codewars REST API endpoints, used to configure caching per endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    User,
+    CompletedChallenges,
+    AuthoredChallenges,
+    CodeChallenge,
+}
+
+/// A structure that holds configuration of the in-memory cache.
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    pub capacity: usize,
+    pub default_ttl: Duration,
+    pub ttls: HashMap<Endpoint, Duration>,
+}
+
+impl CacheConfig {
+    /// Returns a new instance of CacheConfig struct with 1000 entries and 5 minutes TTL.
+    pub fn new() -> Self {
+        CacheConfig {
+            capacity: 1000,
+            default_ttl: Duration::from_secs(5 * 60),
+            ttls: HashMap::new(),
+        }
+    }
+
+    /// Returns the configuration with maximum number of cached responses set.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Returns the configuration with TTL of a single endpoint overridden.
+    pub fn with_ttl(mut self, endpoint: Endpoint, ttl: Duration) -> Self {
+        self.ttls.insert(endpoint, ttl);
+        self
+    }
+
+    /// Returns TTL used for responses of given endpoint.
+    pub fn ttl_for(&self, endpoint: Endpoint) -> Duration {
+        self.ttls
+            .get(&endpoint)
+            .copied()
+            .unwrap_or(self.default_ttl)
+    }
+}
+
+impl Default for CacheConfig {
+    /// Returns a new instance of CacheConfig struct with default values.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A structure that holds cache hit/miss statistics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub expired: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Returns ratio of hits to all lookups, or 0 if there were no lookups.
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// A single cached response.
+struct CacheEntry {
+    value: Value,
+    expires_at: Instant,
+}
+
+/// An LRU cache of API responses keyed by endpoint path and parameters.
+pub struct MemoryCache {
+    entries: LruCache<String, CacheEntry>,
+    config: CacheConfig,
+    stats: CacheStats,
+}
+
+impl MemoryCache {
+    /// Returns a new, empty cache.
+    pub fn new(config: CacheConfig) -> Self {
+        let capacity = NonZeroUsize::new(config.capacity).unwrap_or(NonZeroUsize::MIN);
+        MemoryCache {
+            entries: LruCache::new(capacity),
+            config,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Returns a cached response if it is present and not expired.
+    pub fn get(&mut self, key: &str) -> Option<Value> {
+        let now = Instant::now();
+        match self.entries.get(key) {
+            Some(entry) if entry.expires_at > now => {
+                self.stats.hits += 1;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                self.entries.pop(key);
+                self.stats.expired += 1;
+                self.stats.misses += 1;
+                None
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores a response, using TTL configured for its endpoint.
+    pub fn insert(&mut self, endpoint: Endpoint, key: &str, value: Value) {
+        let ttl = self.config.ttl_for(endpoint);
+        if ttl == Duration::from_secs(0) {
+            return;
+        }
+        let entry = CacheEntry {
+            value,
+            expires_at: Instant::now() + ttl,
+        };
+        if let Some((evicted_key, _)) = self.entries.push(key.to_string(), entry) {
+            if evicted_key != key {
+                self.stats.evictions += 1;
+            }
+        }
+    }
+
+    /// Removes a single response.
+    pub fn invalidate(&mut self, key: &str) {
+        self.entries.pop(key);
+    }
+
+    /// Removes all responses whose key is `path` itself or lies below it.
+    pub fn invalidate_path(&mut self, path: &str) {
+        let keys: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| {
+                key.strip_prefix(path)
+                    .map(|rest| rest.is_empty() || rest.starts_with('/') || rest.starts_with('?'))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+        for key in keys {
+            self.entries.pop(&key);
+        }
+    }
+
+    /// Removes all responses.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns number of cached responses, including expired ones not yet removed.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no cached responses.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns hit/miss statistics collected so far.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+}
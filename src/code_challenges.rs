@@ -1,6 +1,11 @@
+use crate::description::{self, CodeBlock, Example};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::cmp::Ordering;
+use std::str::FromStr;
 
 /// A structure that holds a rank for code challenge.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Rank {
     pub id: i64,
     pub name: String,
@@ -42,6 +47,7 @@ impl Default for Rank {
 }
 
 /// A structure that holds a short details of a user that created code challenge.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CreatedBy {
     pub username: String,
     pub url: String,
@@ -84,6 +90,7 @@ impl Default for CreatedBy {
 }
 
 /// A structure that holds unresolved issues
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Unresolved {
     pub issues: u64,
     pub suggestions: u64,
@@ -124,6 +131,7 @@ impl Default for Unresolved {
 }
 
 /// A structure that holds a single Codewars code challenge.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CodeChallenge {
     pub id: String,
     pub name: String,
@@ -276,7 +284,7 @@ impl CodeChallenge {
         self.unresolved = unresolved;
 
         let mut rank = Rank::new();
-        rank.from_json(&response_json);
+        rank.from_json(response_json);
         self.rank = rank;
 
         let mut created_by = CreatedBy::new();
@@ -301,6 +309,117 @@ impl CodeChallenge {
         }
         self.tags = tags;
     }
+
+    /// Returns code blocks of the description in given language or without a language,
+    /// after removing parts of the description not shown for the language.
+    pub fn code_blocks(&self, language: &str) -> Vec<CodeBlock> {
+        let language = description::normalize(language);
+        description::code_blocks(&description::filter(&self.description, &language))
+            .into_iter()
+            .filter(|block| {
+                block.language.is_empty() || description::normalize(&block.language) == language
+            })
+            .collect()
+    }
+
+    /// Returns example inputs and expected outputs from the description shown for given
+    /// language.
+    pub fn examples(&self, language: &str) -> Vec<Example> {
+        description::examples(&description::filter(&self.description, language))
+    }
+
+    /// Returns share of attempts that end in a completion, from 0 to 1. Zero without
+    /// attempts.
+    pub fn completion_rate(&self) -> f64 {
+        if self.total_attempts == 0 {
+            return 0.0;
+        }
+        (self.total_completed as f64 / self.total_attempts as f64).min(1.0)
+    }
+
+    /// Returns stars per completion. Zero without completions.
+    pub fn star_ratio(&self) -> f64 {
+        if self.total_completed == 0 {
+            return 0.0;
+        }
+        self.total_stars as f64 / self.total_completed as f64
+    }
+
+    /// Returns vote score per completion, from 0 to 1. Zero without completions.
+    pub fn normalized_vote_score(&self) -> f64 {
+        if self.total_completed == 0 {
+            return 0.0;
+        }
+        (self.vote_score as f64 / self.total_completed as f64).min(1.0)
+    }
+
+    /// Returns unresolved issues plus half of unresolved suggestions.
+    pub fn issue_load(&self) -> f64 {
+        self.unresolved.issues as f64 + self.unresolved.suggestions as f64 / 2.0
+    }
+
+    /// Returns a quality score from 0 to 1 combining normalized vote score (40%),
+    /// completion rate (30%) and star ratio (30%, full at one star per ten completions),
+    /// divided by `1 + issue_load / 10`.
+    pub fn quality_score(&self) -> f64 {
+        let score = 0.4 * self.normalized_vote_score()
+            + 0.3 * self.completion_rate()
+            + 0.3 * (self.star_ratio() * 10.0).min(1.0);
+        score / (1.0 + self.issue_load() / 10.0)
+    }
+}
+
+/// A derived metric of code challenges to sort them by.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    CompletionRate,
+    StarRatio,
+    VoteScore,
+    IssueLoad,
+    Quality,
+}
+
+impl SortKey {
+    /// Returns the metric of a code challenge.
+    pub fn value(&self, challenge: &CodeChallenge) -> f64 {
+        match self {
+            SortKey::CompletionRate => challenge.completion_rate(),
+            SortKey::StarRatio => challenge.star_ratio(),
+            SortKey::VoteScore => challenge.normalized_vote_score(),
+            SortKey::IssueLoad => challenge.issue_load(),
+            SortKey::Quality => challenge.quality_score(),
+        }
+    }
+
+    /// Orders code challenges from the best value, which is the lowest issue load and the
+    /// highest of other metrics.
+    pub fn compare(&self, a: &CodeChallenge, b: &CodeChallenge) -> Ordering {
+        let ordering = self.value(b).total_cmp(&self.value(a));
+        match self {
+            SortKey::IssueLoad => ordering.reverse(),
+            _ => ordering,
+        }
+    }
+
+    /// Sorts code challenges from the best value. Equal values keep their order.
+    pub fn sort(&self, challenges: &mut [CodeChallenge]) {
+        challenges.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "completion-rate" => Ok(SortKey::CompletionRate),
+            "star-ratio" => Ok(SortKey::StarRatio),
+            "vote-score" => Ok(SortKey::VoteScore),
+            "issue-load" => Ok(SortKey::IssueLoad),
+            "quality" => Ok(SortKey::Quality),
+            other => Err(format!("Unknown sort key {}", other)),
+        }
+    }
 }
 
 impl Default for CodeChallenge {
@@ -309,3 +428,45 @@ impl Default for CodeChallenge {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::{CodeChallenge, SortKey};
+
+    fn challenge(id: &str, attempts: u64, completed: u64, stars: u64, votes: u64) -> CodeChallenge {
+        let mut challenge = CodeChallenge::new();
+        challenge.id = id.to_string();
+        challenge.total_attempts = attempts;
+        challenge.total_completed = completed;
+        challenge.total_stars = stars;
+        challenge.vote_score = votes;
+        challenge
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut popular = challenge("popular", 1000, 500, 100, 400);
+        assert!((popular.completion_rate() - 0.5).abs() < 1e-9);
+        assert!((popular.star_ratio() - 0.2).abs() < 1e-9);
+        assert!((popular.normalized_vote_score() - 0.8).abs() < 1e-9);
+        assert!((popular.quality_score() - 0.77).abs() < 1e-9);
+        popular.unresolved.issues = 4;
+        popular.unresolved.suggestions = 2;
+        assert!((popular.issue_load() - 5.0).abs() < 1e-9);
+        assert!((popular.quality_score() - 0.77 / 1.5).abs() < 1e-9);
+
+        let new = challenge("new", 0, 0, 0, 0);
+        assert_eq!(new.completion_rate(), 0.0);
+        assert_eq!(new.quality_score(), 0.0);
+
+        let mut challenges = vec![new, popular, challenge("easy", 100, 90, 1, 10)];
+        SortKey::Quality.sort(&mut challenges);
+        let ids: Vec<&str> = challenges.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["popular", "easy", "new"]);
+        SortKey::IssueLoad.sort(&mut challenges);
+        assert_eq!(challenges[2].id, "popular");
+        assert_eq!("star-ratio".parse(), Ok(SortKey::StarRatio));
+        assert!("stars".parse::<SortKey>().is_err());
+    }
+}
@@ -0,0 +1,54 @@
+//! Codewars rank colors and helpers for using them in SVG and terminals.
+
+/// Color of 8 and 7 kyu ranks.
+pub const WHITE: &str = "#e6e6e6";
+/// Color of 6 and 5 kyu ranks.
+pub const YELLOW: &str = "#ecb613";
+/// Color of 4 and 3 kyu ranks.
+pub const BLUE: &str = "#3c7ebb";
+/// Color of 2 and 1 kyu ranks.
+pub const PURPLE: &str = "#866cc7";
+/// Color of dan ranks.
+pub const BLACK: &str = "#555555";
+/// Color of beta challenges.
+pub const RED: &str = "#bb432c";
+/// Color of cells and bars without any value.
+pub const EMPTY: &str = "#303133";
+
+/// Returns the hex color of a rank given its color name as returned by the API, e.g.
+/// `blue`.
+pub fn rank_color(name: &str) -> Option<&'static str> {
+    match name {
+        "white" => Some(WHITE),
+        "yellow" => Some(YELLOW),
+        "blue" => Some(BLUE),
+        "purple" => Some(PURPLE),
+        "black" => Some(BLACK),
+        "red" => Some(RED),
+        _ => None,
+    }
+}
+
+/// Returns a scale from no value to the highest value in kyu rank colors.
+pub fn rank_scale() -> Vec<String> {
+    [EMPTY, WHITE, YELLOW, BLUE, PURPLE]
+        .iter()
+        .map(|color| color.to_string())
+        .collect()
+}
+
+/// Parses a `#rrggbb` color.
+pub fn parse_hex(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |at: usize| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Returns the escape sequence that sets terminal foreground to a `#rrggbb` color.
+pub fn ansi_foreground(color: &str) -> Option<String> {
+    let (r, g, b) = parse_hex(color)?;
+    Some(format!("\x1b[38;2;{};{};{}m", r, g, b))
+}
@@ -0,0 +1,279 @@
+//! Side by side comparison of users.
+
+use crate::user::{OverallRank, User};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const BOLD_GREEN: &str = "\x1b[1;32m";
+const RESET: &str = "\x1b[0m";
+
+/// What a row of a comparison compares.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Metric {
+    OverallRank,
+    Honor,
+    Completed,
+    Authored,
+    LeaderboardPosition,
+    /// Rank in a language.
+    Language(String),
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::OverallRank => write!(f, "overall"),
+            Metric::Honor => write!(f, "honor"),
+            Metric::Completed => write!(f, "completed"),
+            Metric::Authored => write!(f, "authored"),
+            Metric::LeaderboardPosition => write!(f, "leaderboard"),
+            Metric::Language(language) => write!(f, "{}", language),
+        }
+    }
+}
+
+/// A structure that holds values of one metric for every compared user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComparisonRow {
+    pub metric: Metric,
+    /// Values in the order of users, `None` where a user has no value.
+    pub values: Vec<Option<String>>,
+    /// Indexes of users with the best value. More than one on a tie, none if no user has
+    /// a value.
+    pub leaders: Vec<usize>,
+}
+
+/// A structure that holds a comparison of users.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub usernames: Vec<String>,
+    /// Overall rank, honor, completed and authored challenges and leaderboard position,
+    /// followed by languages ordered by the best score in them.
+    pub rows: Vec<ComparisonRow>,
+}
+
+/// Returns a row with leaders chosen by the highest key.
+fn row<K: Ord>(metric: Metric, cells: Vec<Option<(String, K)>>) -> ComparisonRow {
+    let best = cells.iter().flatten().map(|(_, key)| key).max();
+    let leaders = match best {
+        Some(best) => cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.as_ref().map(|(_, key)| key) == Some(best))
+            .map(|(n, _)| n)
+            .collect(),
+        None => vec![],
+    };
+    ComparisonRow {
+        metric,
+        values: cells
+            .iter()
+            .map(|cell| cell.as_ref().map(|(value, _)| value.clone()))
+            .collect(),
+        leaders,
+    }
+}
+
+fn rank_cell(rank: &OverallRank) -> (String, (i64, u64)) {
+    (rank.name.clone(), (rank.rank, rank.score))
+}
+
+/// Returns a comparison of users.
+pub fn compare(users: &[User]) -> Comparison {
+    let count = |value: u64| Some((value.to_string(), value));
+    let mut rows = vec![
+        row(
+            Metric::OverallRank,
+            users
+                .iter()
+                .map(|user| Some(rank_cell(&user.ranks.overall)))
+                .collect(),
+        ),
+        row(
+            Metric::Honor,
+            users
+                .iter()
+                .map(|user| user.honor.map(|honor| (honor.to_string(), honor)))
+                .collect(),
+        ),
+        row(
+            Metric::Completed,
+            users
+                .iter()
+                .map(|user| count(user.code_challenges.total_completed))
+                .collect(),
+        ),
+        row(
+            Metric::Authored,
+            users
+                .iter()
+                .map(|user| count(user.code_challenges.total_authored))
+                .collect(),
+        ),
+        // Lower position is better
+        row(
+            Metric::LeaderboardPosition,
+            users
+                .iter()
+                .map(|user| {
+                    user.leaderboard_position
+                        .map(|position| (format!("#{}", position), -position))
+                })
+                .collect(),
+        ),
+    ];
+
+    let mut best_scores: BTreeMap<&String, u64> = BTreeMap::new();
+    for user in users {
+        for (language, rank) in &user.ranks.languages {
+            let best = best_scores.entry(language).or_insert(0);
+            *best = (*best).max(rank.score);
+        }
+    }
+    let mut languages: Vec<(&String, u64)> = best_scores.into_iter().collect();
+    languages.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    for (language, _) in languages {
+        rows.push(row(
+            Metric::Language(language.clone()),
+            users
+                .iter()
+                .map(|user| user.ranks.languages.get(language).map(rank_cell))
+                .collect(),
+        ));
+    }
+
+    Comparison {
+        usernames: users.iter().map(|user| user.username.clone()).collect(),
+        rows,
+    }
+}
+
+impl Comparison {
+    /// Returns usernames of leaders of every language.
+    pub fn language_leaders(&self) -> BTreeMap<String, Vec<String>> {
+        self.rows
+            .iter()
+            .filter_map(|row| match &row.metric {
+                Metric::Language(language) => Some((
+                    language.clone(),
+                    row.leaders
+                        .iter()
+                        .map(|&n| self.usernames[n].clone())
+                        .collect(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the comparison as a table with a column per user. Leaders are shown in
+    /// bold green with `color`, otherwise marked with `*`.
+    pub fn to_table(&self, color: bool) -> String {
+        let mut header = vec!["".to_string()];
+        header.extend(self.usernames.iter().cloned());
+        let mut table = vec![header];
+        for row in &self.rows {
+            let mut cells = vec![row.metric.to_string()];
+            for (n, value) in row.values.iter().enumerate() {
+                let value = value.clone().unwrap_or_else(|| "-".to_string());
+                let leader = row.leaders.contains(&n) && self.usernames.len() > 1;
+                cells.push(match (leader, color) {
+                    (true, false) => format!("{} *", value),
+                    _ => value,
+                });
+            }
+            table.push(cells);
+        }
+
+        let mut widths = vec![0; self.usernames.len() + 1];
+        for cells in &table {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let mut lines = vec![];
+        for (r, cells) in table.iter().enumerate() {
+            let line: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(c, cell)| {
+                    let padded = format!("{:<width$}", cell, width = widths[c]);
+                    let leader = r > 0
+                        && c > 0
+                        && color
+                        && self.usernames.len() > 1
+                        && self.rows[r - 1].leaders.contains(&(c - 1));
+                    if leader {
+                        format!("{}{}{}", BOLD_GREEN, padded, RESET)
+                    } else {
+                        padded
+                    }
+                })
+                .collect();
+            lines.push(line.join("  ").trim_end().to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{compare, Metric};
+    use crate::user::{OverallRank, User};
+
+    fn rank(rank: i64, score: u64) -> OverallRank {
+        let mut overall_rank = OverallRank::new();
+        overall_rank.rank = rank;
+        overall_rank.name = format!("{} kyu", -rank);
+        overall_rank.score = score;
+        overall_rank
+    }
+
+    fn user(username: &str, honor: i64, position: i64, languages: &[(&str, i64, u64)]) -> User {
+        let mut user = User::new();
+        user.username = username.to_string();
+        user.honor = Some(honor);
+        user.leaderboard_position = Some(position);
+        user.ranks.overall = rank(-5, 100);
+        for (language, language_rank, score) in languages {
+            user.ranks
+                .languages
+                .insert(language.to_string(), rank(*language_rank, *score));
+        }
+        user
+    }
+
+    #[test]
+    fn test_compare() {
+        let users = vec![
+            user("alice", 300, 20, &[("rust", -5, 90), ("python", -7, 20)]),
+            user("bob", 500, 10, &[("rust", -6, 60), ("go", -4, 200)]),
+            user("carol", 500, 30, &[("rust", -5, 90)]),
+        ];
+        let comparison = compare(&users);
+        assert_eq!(comparison.rows[0].metric, Metric::OverallRank);
+        assert_eq!(comparison.rows[0].leaders, vec![0, 1, 2]);
+        assert_eq!(comparison.rows[1].leaders, vec![1, 2]);
+        assert_eq!(comparison.rows[4].values[1], Some("#10".to_string()));
+        assert_eq!(comparison.rows[4].leaders, vec![1]);
+
+        let languages: Vec<String> = comparison.rows[5..]
+            .iter()
+            .map(|row| row.metric.to_string())
+            .collect();
+        assert_eq!(languages, vec!["go", "rust", "python"]);
+        assert_eq!(comparison.rows[6].values[1], Some("6 kyu".to_string()));
+        assert_eq!(comparison.rows[5].values[0], None);
+
+        let leaders = comparison.language_leaders();
+        assert_eq!(leaders["rust"], vec!["alice", "carol"]);
+        assert_eq!(leaders["go"], vec!["bob"]);
+
+        let table = comparison.to_table(false);
+        assert!(table.starts_with("             alice    bob      carol\n"));
+        assert!(table.contains("\nhonor        300      500 *    500 *\n"));
+        assert!(table.contains("\ngo           -        4 kyu *  -"));
+    }
+}
@@ -0,0 +1,859 @@
+//! Codewars-flavored Markdown of code challenge descriptions.
+//!
+//! Descriptions contain blocks shown only for some languages. A fenced block whose
+//! info string is `if:python,ruby` is shown only for those languages and one with
+//! `if-not:java` for all languages but Java. Any of ```` ``` ````, `~~~` and `%%%` can be
+//! used as a fence, so conditional blocks can wrap code blocks. Adjacent code blocks
+//! in different languages are alternatives, of which only the chosen language is kept.
+//!
+//! [`filter`] removes what doesn't apply to a language and [`render`] turns Markdown
+//! into plain text, ANSI-styled text for terminals or HTML. [`code_blocks`] and
+//! [`examples`] extract example code and `input => output` pairs.
+
+use crate::escape::escape_html;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Output of [`render`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Ansi,
+    Html,
+}
+
+/// A fence line: fence character, its count and info string.
+fn fence(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start();
+    let c = trimmed.chars().next()?;
+    if c != '`' && c != '~' && c != '%' {
+        return None;
+    }
+    let len = trimmed.chars().take_while(|&x| x == c).count();
+    if len < 3 {
+        return None;
+    }
+    Some((c, len, trimmed[len..].trim()))
+}
+
+fn is_closing(line: &str, c: char, len: usize) -> bool {
+    matches!(fence(line), Some((close, close_len, "")) if close == c && close_len >= len)
+}
+
+/// Returns canonical name of a language, e.g. `javascript` for `js`.
+pub(crate) fn normalize(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    let canonical = match language.as_str() {
+        "js" | "node" => "javascript",
+        "ts" => "typescript",
+        "py" | "python3" => "python",
+        "rb" => "ruby",
+        "rs" => "rust",
+        "cs" | "c#" => "csharp",
+        "c++" => "cpp",
+        "hs" => "haskell",
+        "kt" => "kotlin",
+        "sh" | "bash" => "shell",
+        _ => return language,
+    };
+    canonical.to_string()
+}
+
+/// Returns whether a block with given info string is shown for a language, or `None` if
+/// the info string is not a condition.
+fn condition(info: &str, language: &str) -> Option<bool> {
+    let language = normalize(language);
+    let matches = |list: &str| list.split(',').any(|l| normalize(l) == language);
+    if let Some(list) = info.strip_prefix("if-not:") {
+        Some(!matches(list))
+    } else {
+        info.strip_prefix("if:").map(matches)
+    }
+}
+
+/// Returns a description with only the parts that apply to a language.
+pub fn filter(markdown: &str, language: &str) -> String {
+    struct Open {
+        c: char,
+        len: usize,
+        conditional: bool,
+        visible: bool,
+    }
+
+    let mut stack: Vec<Open> = vec![];
+    let mut lines = vec![];
+    for line in markdown.lines() {
+        let visible = stack.iter().all(|open| open.visible);
+        let in_code = stack.last().is_some_and(|open| !open.conditional);
+        if let Some((c, len, info)) = fence(line) {
+            if let Some(open) = stack.last() {
+                if is_closing(line, open.c, open.len) {
+                    if !open.conditional && visible {
+                        lines.push(line);
+                    }
+                    stack.pop();
+                    continue;
+                }
+            }
+            if !in_code {
+                match condition(info, language) {
+                    Some(shown) => stack.push(Open {
+                        c,
+                        len,
+                        conditional: true,
+                        visible: shown,
+                    }),
+                    None => {
+                        stack.push(Open {
+                            c,
+                            len,
+                            conditional: false,
+                            visible: true,
+                        });
+                        if visible {
+                            lines.push(line);
+                        }
+                    }
+                }
+                continue;
+            }
+        }
+        if visible {
+            lines.push(line);
+        }
+    }
+    select_code_blocks(&lines, language)
+}
+
+enum Segment<'a> {
+    Line(&'a str),
+    Code {
+        language: String,
+        lines: Vec<&'a str>,
+    },
+}
+
+/// Keeps only the block in given language of adjacent code blocks in different
+/// languages. Blocks are kept as they are if none is in the language.
+fn select_code_blocks(lines: &[&str], language: &str) -> String {
+    let mut segments = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        match fence(lines[i]) {
+            Some((c, len, info)) => {
+                let start = i;
+                i += 1;
+                while i < lines.len() && !is_closing(lines[i], c, len) {
+                    i += 1;
+                }
+                let end = (i + 1).min(lines.len());
+                segments.push(Segment::Code {
+                    language: normalize(info.split_whitespace().next().unwrap_or_default()),
+                    lines: lines[start..end].to_vec(),
+                });
+                i = end;
+            }
+            None => {
+                segments.push(Segment::Line(lines[i]));
+                i += 1;
+            }
+        }
+    }
+
+    let language = normalize(language);
+    let mut output: Vec<&str> = vec![];
+    let mut i = 0;
+    while i < segments.len() {
+        // A group is a run of code blocks with languages, separated by blank lines
+        let mut group = vec![];
+        let mut j = i;
+        while j < segments.len() {
+            match &segments[j] {
+                Segment::Code { language, .. } if !language.is_empty() => group.push(j),
+                Segment::Line(line) if line.trim().is_empty() && !group.is_empty() => {}
+                _ => break,
+            }
+            j += 1;
+        }
+        let alternatives = group
+            .iter()
+            .any(|&k| match (&segments[group[0]], &segments[k]) {
+                (Segment::Code { language: a, .. }, Segment::Code { language: b, .. }) => a != b,
+                _ => false,
+            });
+        if !alternatives {
+            match &segments[i] {
+                Segment::Line(line) => output.push(line),
+                Segment::Code { lines, .. } => output.extend(lines),
+            }
+            i += 1;
+            continue;
+        }
+
+        let chosen: Vec<usize> = group
+            .iter()
+            .cloned()
+            .filter(
+                |&k| matches!(&segments[k], Segment::Code { language: l, .. } if *l == language),
+            )
+            .collect();
+        let kept = if chosen.is_empty() {
+            group.clone()
+        } else {
+            chosen
+        };
+        for (n, &k) in kept.iter().enumerate() {
+            if n > 0 {
+                output.push("");
+            }
+            if let Segment::Code { lines, .. } = &segments[k] {
+                output.extend(lines);
+            }
+        }
+        // Trailing blank lines after the last block of the group are kept
+        i = group[group.len() - 1] + 1;
+    }
+    output.join("\n")
+}
+
+enum Block {
+    Heading(usize, String),
+    Paragraph(Vec<String>),
+    List { ordered: bool, items: Vec<String> },
+    Code { language: String, code: Vec<String> },
+    Quote(Vec<String>),
+    Rule,
+}
+
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    let trimmed = line.trim_start();
+    for marker in &["- ", "* ", "+ "] {
+        if let Some(item) = trimmed.strip_prefix(marker) {
+            return Some((false, item));
+        }
+    }
+    let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        if let Some(item) = trimmed[digits..].strip_prefix(". ") {
+            return Some((true, item));
+        }
+    }
+    None
+}
+
+fn is_rule(line: &str) -> bool {
+    let trimmed: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    trimmed.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|c| trimmed.chars().all(|x| x.to_string() == *c))
+}
+
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut blocks = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            i += 1;
+        } else if let Some((c, len, info)) = fence(line) {
+            let mut code = vec![];
+            i += 1;
+            while i < lines.len() && !is_closing(lines[i], c, len) {
+                code.push(lines[i].to_string());
+                i += 1;
+            }
+            i += 1;
+            blocks.push(Block::Code {
+                language: info
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                code,
+            });
+        } else if trimmed.starts_with('#') {
+            let level = trimmed.chars().take_while(|&c| c == '#').count().min(6);
+            let text = trimmed.trim_start_matches('#').trim().to_string();
+            blocks.push(Block::Heading(level, text));
+            i += 1;
+        } else if is_rule(trimmed) {
+            blocks.push(Block::Rule);
+            i += 1;
+        } else if let Some((ordered, _)) = list_item(line) {
+            let mut items: Vec<String> = vec![];
+            while i < lines.len() && !lines[i].trim().is_empty() {
+                match list_item(lines[i]) {
+                    Some((_, item)) => items.push(item.trim().to_string()),
+                    None => {
+                        let last = items.last_mut().unwrap();
+                        last.push(' ');
+                        last.push_str(lines[i].trim());
+                    }
+                }
+                i += 1;
+            }
+            blocks.push(Block::List { ordered, items });
+        } else if trimmed.starts_with('>') {
+            let mut quote = vec![];
+            while i < lines.len() && lines[i].trim().starts_with('>') {
+                quote.push(lines[i].trim()[1..].trim().to_string());
+                i += 1;
+            }
+            blocks.push(Block::Quote(quote));
+        } else {
+            let mut paragraph = vec![];
+            while i < lines.len() {
+                let line = lines[i];
+                if line.trim().is_empty()
+                    || fence(line).is_some()
+                    || line.trim().starts_with('#')
+                    || (!paragraph.is_empty() && list_item(line).is_some())
+                {
+                    break;
+                }
+                paragraph.push(line.trim().to_string());
+                i += 1;
+            }
+            blocks.push(Block::Paragraph(paragraph));
+        }
+    }
+    blocks
+}
+
+enum Inline {
+    Text(String),
+    Code(String),
+    Strong(String),
+    Emphasis(String),
+    Link { text: String, url: String },
+}
+
+fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut inlines = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let parsed = if c == '`' {
+            rest[1..]
+                .find('`')
+                .map(|end| (Inline::Code(rest[1..end + 1].to_string()), end + 2))
+        } else if rest.starts_with("**") || rest.starts_with("__") {
+            rest[2..]
+                .find(&rest[..2])
+                .map(|end| (Inline::Strong(rest[2..end + 2].to_string()), end + 4))
+        } else if c == '*' && rest[1..].starts_with(|c: char| !c.is_whitespace()) {
+            rest[1..]
+                .find('*')
+                .map(|end| (Inline::Emphasis(rest[1..end + 1].to_string()), end + 2))
+        } else if c == '[' {
+            rest.find("](").and_then(|middle| {
+                rest[middle..].find(')').map(|end| {
+                    (
+                        Inline::Link {
+                            text: rest[1..middle].to_string(),
+                            url: rest[middle + 2..middle + end].to_string(),
+                        },
+                        middle + end + 1,
+                    )
+                })
+            })
+        } else {
+            None
+        };
+        match parsed {
+            Some((inline, consumed)) => {
+                if !plain.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                inlines.push(inline);
+                rest = &rest[consumed..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        inlines.push(Inline::Text(plain));
+    }
+    inlines
+}
+
+/// Returns whether a link target is safe to put in an `href`: a relative URL or an
+/// `http`, `https` or `mailto` URL. Other schemes like `javascript:` and `data:` run code
+/// or embed content when followed. Whitespace and control characters are dropped before
+/// the scheme is read, like browsers do.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    match url.find([':', '/', '?', '#']) {
+        Some(index) if url[index..].starts_with(':') => {
+            let scheme = url[..index].to_ascii_lowercase();
+            scheme == "http" || scheme == "https" || scheme == "mailto"
+        }
+        _ => true,
+    }
+}
+
+fn render_inline(text: &str, format: Format) -> String {
+    let mut output = String::new();
+    for inline in parse_inline(text) {
+        let _ = match (format, inline) {
+            (Format::Html, Inline::Text(text)) => write!(output, "{}", escape_html(&text)),
+            (Format::Html, Inline::Code(code)) => {
+                write!(output, "<code>{}</code>", escape_html(&code))
+            }
+            (Format::Html, Inline::Strong(text)) => {
+                write!(output, "<strong>{}</strong>", escape_html(&text))
+            }
+            (Format::Html, Inline::Emphasis(text)) => {
+                write!(output, "<em>{}</em>", escape_html(&text))
+            }
+            (Format::Html, Inline::Link { text, url }) if is_safe_url(&url) => write!(
+                output,
+                "<a href=\"{}\">{}</a>",
+                escape_html(&url),
+                escape_html(&text)
+            ),
+            (Format::Html, Inline::Link { text, .. }) => write!(output, "{}", escape_html(&text)),
+            (Format::Ansi, Inline::Code(code)) => write!(output, "{}{}{}", CYAN, code, RESET),
+            (Format::Ansi, Inline::Strong(text)) => write!(output, "{}{}{}", BOLD, text, RESET),
+            (Format::Ansi, Inline::Emphasis(text)) => {
+                write!(output, "{}{}{}", ITALIC, text, RESET)
+            }
+            (Format::Ansi, Inline::Link { text, url }) => write!(
+                output,
+                "{}{}{} {}({}){}",
+                UNDERLINE, text, RESET, DIM, url, RESET
+            ),
+            (_, Inline::Link { text, url }) => write!(output, "{} ({})", text, url),
+            (_, Inline::Text(text))
+            | (_, Inline::Code(text))
+            | (_, Inline::Strong(text))
+            | (_, Inline::Emphasis(text)) => write!(output, "{}", text),
+        };
+    }
+    output
+}
+
+/// Renders Markdown in given format. Raw HTML in the description is escaped in HTML
+/// output and kept as is otherwise.
+pub fn render(markdown: &str, format: Format) -> String {
+    let mut rendered = vec![];
+    for block in parse_blocks(markdown) {
+        let inline = |text: &str| render_inline(text, format);
+        let text = match (format, block) {
+            (Format::Html, Block::Heading(level, text)) => {
+                format!("<h{0}>{1}</h{0}>", level, inline(&text))
+            }
+            (Format::Html, Block::Paragraph(lines)) => {
+                format!("<p>{}</p>", inline(&lines.join("\n")))
+            }
+            (Format::Html, Block::List { ordered, items }) => {
+                let tag = if ordered { "ol" } else { "ul" };
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| format!("<li>{}</li>", inline(item)))
+                    .collect();
+                format!("<{0}>\n{1}\n</{0}>", tag, items.join("\n"))
+            }
+            (Format::Html, Block::Code { language, code }) => {
+                let class = if language.is_empty() {
+                    String::new()
+                } else {
+                    format!(" class=\"language-{}\"", escape_html(&language))
+                };
+                format!(
+                    "<pre><code{}>{}</code></pre>",
+                    class,
+                    escape_html(&code.join("\n"))
+                )
+            }
+            (Format::Html, Block::Quote(lines)) => {
+                format!(
+                    "<blockquote><p>{}</p></blockquote>",
+                    inline(&lines.join("\n"))
+                )
+            }
+            (Format::Html, Block::Rule) => "<hr>".to_string(),
+            (Format::Ansi, Block::Heading(_, text)) => {
+                format!("{}{}{}{}", BOLD, UNDERLINE, inline(&text), RESET)
+            }
+            (Format::Ansi, Block::Code { code, .. }) => code
+                .iter()
+                .map(|line| format!("    {}{}{}", CYAN, line, RESET))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            (_, Block::Heading(_, text)) => inline(&text),
+            (_, Block::Paragraph(lines)) => inline(&lines.join("\n")),
+            (_, Block::List { ordered, items }) => items
+                .iter()
+                .enumerate()
+                .map(|(n, item)| match ordered {
+                    true => format!("{}. {}", n + 1, inline(item)),
+                    false => format!("- {}", inline(item)),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            (_, Block::Code { code, .. }) => code
+                .iter()
+                .map(|line| format!("    {}", line).trim_end().to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+            (_, Block::Quote(lines)) => lines
+                .iter()
+                .map(|line| format!("  {}", inline(line)))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            (_, Block::Rule) => "---".to_string(),
+        };
+        rendered.push(text);
+    }
+    rendered.join("\n\n")
+}
+
+/// A structure that holds a fenced code block of a description.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// Language of the fence info string, empty if the block has none.
+    pub language: String,
+    pub code: String,
+}
+
+/// A structure that holds an example input and the expected output, e.g. `"(){}"` and
+/// `true` for `"(){}" => true`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Example {
+    pub input: String,
+    pub output: String,
+}
+
+/// Returns code blocks of a description in order.
+pub fn code_blocks(markdown: &str) -> Vec<CodeBlock> {
+    parse_blocks(markdown)
+        .into_iter()
+        .filter_map(|block| match block {
+            Block::Code { language, code } => Some(CodeBlock {
+                language,
+                code: code.join("\n"),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+const SEPARATORS: &[&str] = &[
+    "==>",
+    "-->",
+    "=>",
+    " should return ",
+    " should equal ",
+    " returns ",
+    " == ",
+];
+
+const ASSERTIONS: &[&str] = &[
+    "assert_equals(",
+    "assert_eq!(",
+    "assertEquals(",
+    "assertEqual(",
+    "assert.equal(",
+    "assert.strictEqual(",
+    "assert.deepEqual(",
+    "assert.deepStrictEqual(",
+];
+
+/// Returns examples found in a description. Lines of the form `input => output` (also
+/// with `-->`, `==>`, `==`, `should return` and `returns`) and two-argument equality
+/// assertions such as `assert_equals(input, output)` are recognized. Descriptions
+/// should be filtered for a language first, so examples of other languages are
+/// skipped.
+pub fn examples(markdown: &str) -> Vec<Example> {
+    let mut examples = vec![];
+    for line in markdown.lines() {
+        if let Some(example) = example(line) {
+            if !examples.contains(&example) {
+                examples.push(example);
+            }
+        }
+    }
+    examples
+}
+
+fn strip_comment(text: &str) -> &str {
+    let text = text.trim();
+    for marker in &["//", "#", "--"] {
+        if let Some(stripped) = text.strip_prefix(marker) {
+            return stripped.trim();
+        }
+    }
+    text
+}
+
+fn example(line: &str) -> Option<Example> {
+    let mut line = line.trim();
+    if let Some((_, item)) = list_item(line) {
+        line = item.trim();
+    }
+    let line = strip_comment(line).trim_matches('`').trim();
+
+    let (input, output) = match ASSERTIONS
+        .iter()
+        .find_map(|a| line.find(a).map(|at| (at, a)))
+    {
+        Some((at, assertion)) => {
+            // Only a `)` after the assertion can close it
+            let arguments = &line[at + assertion.len()..];
+            let arguments = &arguments[..arguments.rfind(')')?];
+            let mut arguments = split_arguments(arguments).into_iter();
+            (arguments.next()?, arguments.next()?)
+        }
+        None => {
+            let (at, separator) = SEPARATORS
+                .iter()
+                .find_map(|s| line.find(s).map(|at| (at, s)))?;
+            let input = line[..at]
+                .trim()
+                .trim_start_matches("assert ")
+                .trim_end_matches(['#', '/', '-'])
+                .trim();
+            let mut output = strip_comment(&line[at + separator.len()..]);
+            // Output in a code span of its own, followed by text
+            if let Some(code) = output.strip_prefix('`') {
+                output = code.split('`').next().unwrap_or_default();
+            }
+            // Explanations often follow the output as a comment
+            for comment in &[" //", " # "] {
+                if let Some(end) = output.find(comment) {
+                    output = &output[..end];
+                }
+            }
+            (input, output)
+        }
+    };
+    let input = input.trim().trim_matches('`').trim();
+    let output = output
+        .trim()
+        .trim_end_matches([',', ';'])
+        .trim_matches('`')
+        .trim();
+
+    let declaration = [
+        "const ", "let ", "var ", "function", "def ", "fn ", "if ", "case ",
+    ]
+    .iter()
+    .any(|keyword| input.starts_with(keyword));
+    if input.is_empty() || output.is_empty() || declaration || output.ends_with('{') {
+        return None;
+    }
+    Some(Example {
+        input: input.to_string(),
+        output: output.to_string(),
+    })
+}
+
+/// Splits arguments of a call at top-level commas.
+fn split_arguments(arguments: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in arguments.char_indices() {
+        match (quote, c) {
+            (Some(_), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') | (None, '[') | (None, '{') => depth += 1,
+            (None, ')') | (None, ']') | (None, '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(arguments[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+    parts.push(arguments[start..].trim());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{code_blocks, examples, filter, render, Example, Format};
+
+    #[test]
+    fn test_filter_conditional_blocks() {
+        let markdown = "Intro\n\
+            ```if:python,ruby\n\
+            Python or Ruby\n\
+            ```\n\
+            ~~~if-not:java\n\
+            Not Java\n\
+            ~~~\n\
+            %%%if:js\n\
+            JavaScript\n\
+            %%%\n\
+            Outro";
+        assert_eq!(
+            filter(markdown, "python"),
+            "Intro\nPython or Ruby\nNot Java\nOutro"
+        );
+        assert_eq!(filter(markdown, "java"), "Intro\nOutro");
+        assert_eq!(
+            filter(markdown, "javascript"),
+            "Intro\nNot Java\nJavaScript\nOutro"
+        );
+    }
+
+    #[test]
+    fn test_filter_nested_blocks() {
+        let markdown = "~~~if:python\n\
+            ```python\n\
+            ~~~\n\
+            ```\n\
+            ~~~\n\
+            ```if-not:python\n\
+            ~~~if:rust\n\
+            Rust\n\
+            ~~~\n\
+            ```";
+        // Fence inside a code block is code, not the end of the conditional block
+        assert_eq!(filter(markdown, "python"), "```python\n~~~\n```");
+        assert_eq!(filter(markdown, "rust"), "Rust");
+        assert_eq!(filter(markdown, "go"), "");
+    }
+
+    #[test]
+    fn test_filter_code_alternatives() {
+        let markdown = "Examples:\n\
+            ```python\n\
+            solution(1)\n\
+            ```\n\
+            \n\
+            ```javascript\n\
+            solution(1);\n\
+            ```\n\
+            \n\
+            Text";
+        assert_eq!(
+            filter(markdown, "js"),
+            "Examples:\n```javascript\nsolution(1);\n```\n\nText"
+        );
+        // All alternatives are kept for other languages
+        assert_eq!(filter(markdown, "go"), markdown);
+        // A single code block is kept whatever its language
+        assert_eq!(filter("```ruby\nx\n```", "python"), "```ruby\nx\n```");
+    }
+
+    #[test]
+    fn test_render() {
+        let markdown = "# Task\n\
+            Return **true** if `s` is *valid*, see [docs](https://example.com).\n\
+            \n\
+            - one\n\
+            - two <b>\n\
+            \n\
+            ```python\n\
+            a < b\n\
+            ```";
+        assert_eq!(
+            render(markdown, Format::Text),
+            "Task\n\n\
+            Return true if s is valid, see docs (https://example.com).\n\n\
+            - one\n- two <b>\n\n    a < b"
+        );
+        assert_eq!(
+            render(markdown, Format::Html),
+            "<h1>Task</h1>\n\n\
+            <p>Return <strong>true</strong> if <code>s</code> is <em>valid</em>, see \
+            <a href=\"https://example.com\">docs</a>.</p>\n\n\
+            <ul>\n<li>one</li>\n<li>two &lt;b&gt;</li>\n</ul>\n\n\
+            <pre><code class=\"language-python\">a &lt; b</code></pre>"
+        );
+        let ansi = render(markdown, Format::Ansi);
+        assert!(ansi.starts_with("\x1b[1m\x1b[4mTask\x1b[0m"));
+        assert!(ansi.contains("\x1b[1mtrue\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_unsafe_links() {
+        assert_eq!(
+            render("[click](javascript:document.cookie)", Format::Html),
+            "<p>click</p>"
+        );
+        assert_eq!(
+            render("[click](JavaScript:document.cookie)", Format::Html),
+            "<p>click</p>"
+        );
+        assert_eq!(
+            render("[img](data:text/html;base64,PHNjcmlwdD4=)", Format::Html),
+            "<p>img</p>"
+        );
+        assert_eq!(
+            render("[mail](mailto:a@example.com) [up](../kata)", Format::Html),
+            "<p><a href=\"mailto:a@example.com\">mail</a> <a href=\"../kata\">up</a></p>"
+        );
+    }
+
+    #[test]
+    fn test_examples() {
+        let markdown = "Examples:\n\
+            \n\
+            * `\"(){}[]\"   =>  True`\n\
+            * `\"([{}])\"` --> `True`, nested\n\
+            \n\
+            ```python\n\
+            solution(10) # should return 23\n\
+            valid_braces(\"[(])\") == False  # wrong order\n\
+            test.assert_equals(valid_braces(\"(}\"), False)\n\
+            ```\n\
+            ~~~javascript\n\
+            const f = (a) => {\n\
+            ~~~";
+        let found = examples(markdown);
+        let pair = |input: &str, output: &str| Example {
+            input: input.to_string(),
+            output: output.to_string(),
+        };
+        assert_eq!(
+            found,
+            vec![
+                pair("\"(){}[]\"", "True"),
+                pair("\"([{}])\"", "True"),
+                pair("solution(10)", "23"),
+                pair("valid_braces(\"[(])\")", "False"),
+                pair("valid_braces(\"(}\")", "False"),
+            ]
+        );
+
+        let blocks = code_blocks(markdown);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].language, "python");
+        assert!(blocks[1].code.starts_with("const f"));
+    }
+
+    #[test]
+    fn test_examples_unclosed_assertion() {
+        let markdown = "```rust\nf(1); assert_eq!(x\n```";
+        assert!(examples(markdown).is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A structure that holds a single response stored on disk together with its validators.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub stored_at: u64,
+    pub body: Value,
+}
+
+impl CachedResponse {
+    /// Returns a new instance of CachedResponse struct stored at current time.
+    pub fn new(
+        url: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: Value,
+    ) -> Self {
+        CachedResponse {
+            url,
+            etag,
+            last_modified,
+            stored_at: unix_now(),
+            body,
+        }
+    }
+}
+
+/// A persistent cache of API responses, one JSON file per request path.
+///
+/// Responses are revalidated with `If-None-Match`/`If-Modified-Since` so unchanged
+/// resources are answered with `304 Not Modified` and served from disk.
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Returns a new instance of DiskCache struct. The directory is created on first write.
+    pub fn new(dir: PathBuf) -> Self {
+        DiskCache { dir }
+    }
+
+    /// Returns the directory responses are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns a stored response for a request path, if there is a readable one.
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let contents = fs::read_to_string(self.path_for(key)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Stores a response for a request path, replacing the previous one.
+    pub fn store(&self, key: &str, response: &CachedResponse) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let contents = serde_json::to_string(response)?;
+        // Write to a temporary file first so readers never see a partial response
+        let path = self.path_for(key);
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(temp_path, path)
+    }
+
+    /// Removes a stored response for a request path.
+    pub fn remove(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path_for(key)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes all stored responses and temporary files left by interrupted writes.
+    pub fn clear(&self) -> io::Result<()> {
+        if !self.dir.is_dir() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if name.ends_with(".json") || name.ends_with(".json.tmp") {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns file path for a request path. Characters other than ASCII alphanumerics,
+    /// `-` and `.` are escaped as `_XX` so different paths never share a file.
+    fn path_for(&self, key: &str) -> PathBuf {
+        let mut file_name = String::with_capacity(key.len() + 5);
+        for byte in key.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' => {
+                    file_name.push(byte as char)
+                }
+                _ => file_name.push_str(&format!("_{:02X}", byte)),
+            }
+        }
+        file_name.push_str(".json");
+        self.dir.join(file_name)
+    }
+}
+
+/// Returns number of seconds since Unix epoch.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_clear_removes_temporary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path().to_path_buf());
+        let response = CachedResponse::new("url".to_string(), None, None, Value::Null);
+        cache.store("/users/some_user", &response).unwrap();
+        fs::write(dir.path().join("_2Fusers_2Fother.json.tmp"), "{").unwrap();
+        fs::write(dir.path().join("notes.txt"), "keep").unwrap();
+
+        cache.clear().unwrap();
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["notes.txt".to_string()]);
+    }
+}
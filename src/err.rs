@@ -1,4 +1,7 @@
 use reqwest::Error as ReqError;
+#[cfg(feature = "store")]
+use rusqlite::Error as SqliteError;
+use serde_json::Error as SerdeJsonError;
 use snafu::Snafu;
 
 #[derive(Snafu, Debug)]
@@ -7,6 +10,9 @@ pub enum Error {
     #[snafu(display("{}", source))]
     ReqwestError { source: ReqError },
 
+    #[snafu(display("{}", source))]
+    JsonError { source: SerdeJsonError },
+
     #[snafu(display("Error in retriving user data"))]
     CodewarsError { message: String },
 
@@ -15,4 +21,32 @@ pub enum Error {
 
     #[snafu(display("Challenge {} not found", challenge_title))]
     ChallengeNotFound { challenge_title: String },
+
+    #[snafu(display("{} is not cached and client is offline", url))]
+    NotCached { url: String },
+
+    #[snafu(display("Webhook secret is missing or doesn't match"))]
+    InvalidWebhookSecret,
+
+    #[snafu(display("{}", source))]
+    IoError { source: std::io::Error },
+
+    #[snafu(display("Challenge {} is not available in {}", challenge_title, language))]
+    LanguageNotSupported {
+        language: String,
+        challenge_title: String,
+    },
+
+    #[snafu(display("No template for {}", language))]
+    TemplateNotFound { language: String },
+
+    #[snafu(display("Invalid kata {}: {}", kata, reason))]
+    InvalidKataRef { kata: String, reason: String },
+
+    #[snafu(display("Invalid search query {}: {}", query, reason))]
+    InvalidQuery { query: String, reason: String },
+
+    #[cfg(feature = "store")]
+    #[snafu(display("Store error: {}", source))]
+    StoreError { source: SqliteError },
 }
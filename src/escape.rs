@@ -0,0 +1,10 @@
+//! Escaping of text embedded in generated HTML and SVG.
+
+/// Returns text with characters that have a meaning in HTML replaced by entities, for
+/// use in element content and quoted attributes.
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,251 @@
+//! Calendar heatmap of completions, rendered as SVG or in a terminal.
+//!
+//! The calendar has a column per week, starting on Monday, and a row per weekday.
+//! Days are colored by their number of completions relative to the busiest day.
+
+use crate::colors;
+use crate::user_challenges::CompletedChallenge;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const CELL: i64 = 10;
+const STEP: i64 = 12;
+const LEFT: i64 = 30;
+const TOP: i64 = 16;
+const WEEKDAYS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+const BLOCKS: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// A structure that holds completions per day and how to render them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    counts: BTreeMap<NaiveDate, usize>,
+    start: NaiveDate,
+    end: NaiveDate,
+    scale: Vec<String>,
+}
+
+impl Heatmap {
+    /// Returns a heatmap of the last 52 weeks in rank colors.
+    pub fn new(completed: &[CompletedChallenge]) -> Self {
+        let end = Utc::now().date_naive();
+        let mut counts = BTreeMap::new();
+        for completed_at in completed.iter().filter_map(|c| c.completed_at_time()) {
+            *counts.entry(completed_at.date_naive()).or_insert(0) += 1;
+        }
+        Heatmap {
+            counts,
+            start: end - Duration::weeks(52) + Duration::days(1),
+            end,
+            scale: colors::rank_scale(),
+        }
+    }
+
+    /// Shows days from `start` to `end`, both inclusive. Dates given in reverse order are
+    /// swapped.
+    pub fn with_range(mut self, start: NaiveDate, end: NaiveDate) -> Self {
+        self.start = start.min(end);
+        self.end = start.max(end);
+        self
+    }
+
+    /// Sets `#rrggbb` colors from no completions to the busiest day. The scale needs at
+    /// least two colors; a shorter one is ignored.
+    pub fn with_scale(mut self, scale: Vec<String>) -> Self {
+        if scale.len() >= 2 {
+            self.scale = scale;
+        }
+        self
+    }
+
+    /// Returns the number of completions on a day.
+    pub fn count(&self, day: NaiveDate) -> usize {
+        self.counts.get(&day).cloned().unwrap_or(0)
+    }
+
+    /// Returns the index into the color scale of a day. Zero means no completions.
+    pub fn level(&self, day: NaiveDate) -> usize {
+        let count = self.count(day);
+        let max = self
+            .counts
+            .range(self.start..=self.end)
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0);
+        if count == 0 || max == 0 {
+            return 0;
+        }
+        let levels = self.scale.len() - 1;
+        ((count * levels).div_ceil(max)).clamp(1, levels)
+    }
+
+    /// Returns weeks of the range, each with seven days from Monday. Days outside the
+    /// range are `None`.
+    fn weeks(&self) -> Vec<[Option<NaiveDate>; 7]> {
+        let mut weeks = vec![];
+        let mut monday =
+            self.start - Duration::days(self.start.weekday().num_days_from_monday() as i64);
+        while monday <= self.end {
+            let mut week = [None; 7];
+            for (offset, day) in week.iter_mut().enumerate() {
+                let date = monday + Duration::days(offset as i64);
+                if date >= self.start && date <= self.end {
+                    *day = Some(date);
+                }
+            }
+            weeks.push(week);
+            monday += Duration::weeks(1);
+        }
+        weeks
+    }
+
+    /// Returns the heatmap as a standalone SVG image.
+    pub fn to_svg(&self) -> String {
+        let weeks = self.weeks();
+        let width = LEFT + weeks.len() as i64 * STEP;
+        let height = TOP + 7 * STEP;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"9\" fill=\"#888888\">",
+            width, height
+        );
+        for (row, label) in WEEKDAYS.iter().enumerate() {
+            if !label.is_empty() {
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"0\" y=\"{}\">{}</text>",
+                    TOP + row as i64 * STEP + CELL - 1,
+                    label
+                );
+            }
+        }
+        let mut month = None;
+        for (column, week) in weeks.iter().enumerate() {
+            let x = LEFT + column as i64 * STEP;
+            // Label a month above the first week that starts in it
+            if let Some(first) = week.iter().flatten().next() {
+                if month != Some(first.month()) {
+                    month = Some(first.month());
+                    let _ = writeln!(
+                        svg,
+                        "<text x=\"{}\" y=\"{}\">{}</text>",
+                        x,
+                        TOP - 6,
+                        first.format("%b")
+                    );
+                }
+            }
+            for (row, day) in week.iter().enumerate() {
+                if let Some(day) = day {
+                    let _ = writeln!(
+                        svg,
+                        "<rect x=\"{x}\" y=\"{y}\" width=\"{size}\" height=\"{size}\" rx=\"2\" \
+                         fill=\"{fill}\"><title>{day}: {count} completed</title></rect>",
+                        x = x,
+                        y = TOP + row as i64 * STEP,
+                        size = CELL,
+                        fill = self.scale[self.level(*day)],
+                        day = day,
+                        count = self.count(*day)
+                    );
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Returns the heatmap as lines of text for a terminal. With `color` days are drawn
+    /// in scale colors using 24-bit ANSI escape sequences, otherwise with shaded blocks.
+    pub fn to_terminal(&self, color: bool) -> String {
+        let weeks = self.weeks();
+        let levels = self.scale.len() - 1;
+        let mut lines = vec![];
+        for (row, label) in WEEKDAYS.iter().enumerate() {
+            let mut line = format!("{:<4}", label);
+            for week in &weeks {
+                let cell = match week[row] {
+                    None => " ".to_string(),
+                    Some(day) if color => {
+                        let level = self.level(day);
+                        match colors::ansi_foreground(&self.scale[level]) {
+                            Some(escape) => format!("{}■\x1b[0m", escape),
+                            None => BLOCKS[level * (BLOCKS.len() - 1) / levels].to_string(),
+                        }
+                    }
+                    Some(day) => BLOCKS[self.level(day) * (BLOCKS.len() - 1) / levels].to_string(),
+                };
+                line.push_str(&cell);
+                line.push(' ');
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Heatmap;
+    use crate::user_challenges::CompletedChallenge;
+    use chrono::NaiveDate;
+
+    fn completed(completed_at: &str) -> CompletedChallenge {
+        let mut challenge = CompletedChallenge::new();
+        challenge.completed_at = completed_at.to_string();
+        challenge
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let day = |d| NaiveDate::from_ymd_opt(2020, 1, d).unwrap();
+        let heatmap = Heatmap::new(&[
+            completed("2020-01-01T10:00:00Z"),
+            completed("2020-01-01T11:00:00Z"),
+            completed("2020-01-01T12:00:00Z"),
+            completed("2020-01-01T13:00:00Z"),
+            completed("2020-01-02T10:00:00Z"),
+            completed("2020-01-13T10:00:00Z"),
+            completed("2020-01-13T11:00:00Z"),
+        ])
+        .with_range(day(1), day(14));
+
+        assert_eq!(heatmap.count(day(1)), 4);
+        assert_eq!(heatmap.level(day(1)), 4);
+        assert_eq!(heatmap.level(day(2)), 1);
+        assert_eq!(heatmap.level(day(13)), 2);
+        assert_eq!(heatmap.level(day(3)), 0);
+
+        // 2020-01-01 is a Wednesday, so the first week is partial
+        let svg = heatmap.to_svg();
+        assert_eq!(svg.matches("<rect").count(), 14);
+        assert!(svg.contains("<title>2020-01-01: 4 completed</title>"));
+        assert!(svg.contains(">Jan</text>"));
+        assert_eq!(svg, heatmap.to_svg());
+
+        let terminal = heatmap.to_terminal(false);
+        let lines: Vec<&str> = terminal.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "Mon   · ▒");
+        assert_eq!(lines[2], "Wed █ ·");
+        assert_eq!(lines[3], "    ░ ·");
+        assert!(heatmap
+            .to_terminal(true)
+            .contains("\x1b[38;2;134;108;199m■"));
+    }
+
+    #[test]
+    fn test_heatmap_reversed_range() {
+        let day = |d| NaiveDate::from_ymd_opt(2020, 1, d).unwrap();
+        let completed = [completed("2020-01-02T10:00:00Z")];
+        let heatmap = Heatmap::new(&completed).with_range(day(14), day(1));
+        assert_eq!(
+            heatmap,
+            Heatmap::new(&completed).with_range(day(1), day(14))
+        );
+        assert_eq!(heatmap.level(day(2)), 4);
+    }
+}
@@ -0,0 +1,194 @@
+//! Joining completed challenges with their details.
+//!
+//! The completed challenges endpoint returns only ids, names and languages. Rank,
+//! category and tags need a code challenge request per kata. [`Hydrator`] fetches them
+//! with `Codewars::get_code_challenges`, so the client's concurrency and rate limit
+//! apply, and remembers fetched challenges, so re-hydrating a growing history only
+//! fetches new katas.
+
+use crate::code_challenges::{CodeChallenge, Rank};
+use crate::codewars::Codewars;
+use crate::err::Error;
+use crate::user_challenges::CompletedChallenge;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A structure that holds a completed challenge with its details, if they could be
+/// retrieved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HydratedChallenge {
+    pub completed: CompletedChallenge,
+    pub challenge: Option<CodeChallenge>,
+}
+
+/// A structure that holds the result of [`Hydrator::hydrate`].
+#[derive(Debug, Default)]
+pub struct Hydrated {
+    /// Challenges in the order they were given.
+    pub challenges: Vec<HydratedChallenge>,
+    /// Ids of challenges whose details couldn't be retrieved, with the reason. Retired
+    /// challenges fail with `Error::ChallengeNotFound`.
+    pub errors: Vec<(String, Error)>,
+}
+
+impl Hydrated {
+    /// Returns distribution of challenges with details by rank, category and tag.
+    pub fn distribution(&self) -> Distribution {
+        Distribution::new(&self.challenges)
+    }
+}
+
+/// A structure that holds the number of completed challenges of a rank.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RankCount {
+    pub rank: Rank,
+    pub count: usize,
+}
+
+/// A structure that holds the number of completed challenges by rank, category and tag.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Distribution {
+    /// Counts per rank from the easiest to the hardest rank.
+    pub by_rank: Vec<RankCount>,
+    pub by_category: BTreeMap<String, usize>,
+    /// Counts per tag. A challenge counts for each of its tags.
+    pub by_tag: BTreeMap<String, usize>,
+    /// Number of challenges without details.
+    pub unknown: usize,
+}
+
+impl Distribution {
+    /// Returns distribution of hydrated challenges.
+    pub fn new(challenges: &[HydratedChallenge]) -> Self {
+        let mut distribution = Distribution::default();
+        let mut by_rank: BTreeMap<i64, RankCount> = BTreeMap::new();
+        for hydrated in challenges {
+            let challenge = match &hydrated.challenge {
+                Some(challenge) => challenge,
+                None => {
+                    distribution.unknown += 1;
+                    continue;
+                }
+            };
+            by_rank
+                .entry(challenge.rank.id)
+                .or_insert_with(|| RankCount {
+                    rank: challenge.rank.clone(),
+                    count: 0,
+                })
+                .count += 1;
+            *distribution
+                .by_category
+                .entry(challenge.category.clone())
+                .or_insert(0) += 1;
+            for tag in &challenge.tags {
+                *distribution.by_tag.entry(tag.clone()).or_insert(0) += 1;
+            }
+        }
+        distribution.by_rank = by_rank.into_values().collect();
+        distribution
+    }
+
+    /// Returns the rank with most completed challenges. Ties go to the harder rank.
+    pub fn most_common_rank(&self) -> Option<&Rank> {
+        self.by_rank
+            .iter()
+            .rev()
+            .max_by_key(|rank_count| rank_count.count)
+            .map(|rank_count| &rank_count.rank)
+    }
+}
+
+/// Fetches details of completed challenges.
+pub struct Hydrator<'a> {
+    codewars: &'a Codewars,
+    challenges: HashMap<String, CodeChallenge>,
+}
+
+impl<'a> Hydrator<'a> {
+    /// Returns a new hydrator. Concurrency and rate limit are set on `codewars`, see
+    /// `Codewars::with_concurrency` and `Codewars::with_rate_limit`.
+    pub fn new(codewars: &'a Codewars) -> Self {
+        Hydrator {
+            codewars,
+            challenges: HashMap::new(),
+        }
+    }
+
+    /// Returns completed challenges joined with their details. Details fetched by
+    /// earlier calls are reused; failed challenges are retried by the next call.
+    pub fn hydrate(&mut self, completed: &[CompletedChallenge]) -> Hydrated {
+        let missing: Vec<&str> = completed
+            .iter()
+            .map(|challenge| challenge.id.as_str())
+            .filter(|id| !self.challenges.contains_key(*id))
+            .collect();
+
+        let mut errors = vec![];
+        for (id, result) in self.codewars.get_code_challenges(&missing) {
+            match result {
+                Ok(challenge) => {
+                    self.challenges.insert(id, challenge);
+                }
+                Err(e) => errors.push((id, e)),
+            }
+        }
+
+        let challenges = completed
+            .iter()
+            .map(|completed| HydratedChallenge {
+                completed: completed.clone(),
+                challenge: self.challenges.get(&completed.id).cloned(),
+            })
+            .collect();
+        Hydrated { challenges, errors }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Hydrator;
+    use crate::codewars::Codewars;
+    use crate::err::Error;
+    use codewars_mock::MockServerBuilder;
+    use std::path::Path;
+    use std::time::Duration;
+
+    #[test]
+    fn test_hydrate_distribution() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let server = MockServerBuilder::from_dir(&data_dir)
+            .unwrap()
+            .start()
+            .unwrap();
+        let codewars = Codewars::with_base_url("".to_string(), server.base_url())
+            .with_concurrency(2)
+            .with_rate_limit(Duration::from_millis(10));
+        let completed = codewars
+            .get_completed_challenges("some_user".to_string())
+            .unwrap();
+        let mut hydrator = Hydrator::new(&codewars);
+
+        let hydrated = hydrator.hydrate(&completed);
+        assert_eq!(hydrated.challenges.len(), 3);
+        assert_eq!(hydrated.errors.len(), 2);
+        assert!(matches!(
+            hydrated.errors[0].1,
+            Error::ChallengeNotFound { .. }
+        ));
+
+        let distribution = hydrated.distribution();
+        assert_eq!(distribution.unknown, 2);
+        assert_eq!(distribution.by_rank.len(), 1);
+        assert_eq!(distribution.by_rank[0].rank.name, "4 kyu");
+        assert_eq!(distribution.by_category["algorithms"], 1);
+        assert_eq!(distribution.by_tag["Validation"], 1);
+        assert_eq!(distribution.most_common_rank().unwrap().id, -4);
+
+        // Only challenges that failed are requested again
+        let requests = server.requests().len();
+        hydrator.hydrate(&completed);
+        assert_eq!(server.requests().len(), requests + 2);
+    }
+}
@@ -0,0 +1,185 @@
+//! References to code challenges by id, slug or URL.
+//!
+//! Codewars accepts an id like `5277c8a221e209d3f6000b56` or a slug like
+//! `valid-braces` after `/code-challenges/`. [`KataRef`] also takes kata URLs as shown
+//! in a browser, e.g. `https://www.codewars.com/kata/valid-braces/train/rust`, and
+//! remembers the language of training URLs.
+
+use crate::err::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A structure that holds a validated reference to a code challenge.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KataRef {
+    /// Id or slug, not encoded.
+    pub id_or_slug: String,
+    /// Language of a `/train/<language>` URL.
+    pub language: Option<String>,
+}
+
+/// Returns `%XX` encoding of every byte except unreserved characters of RFC 3986.
+fn percent_encode(segment: &str) -> String {
+    let mut encoded = String::new();
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Returns a path segment with `%XX` sequences decoded, or `None` if a sequence is
+/// invalid or the result isn't UTF-8.
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = vec![];
+    let mut n = 0;
+    while n < bytes.len() {
+        if bytes[n] == b'%' {
+            let hex = segment.get(n + 1..n + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            n += 3;
+        } else {
+            decoded.push(bytes[n]);
+            n += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+impl KataRef {
+    /// Parses an id, a slug or a kata URL such as
+    /// `https://www.codewars.com/kata/<id-or-slug>[/train/<language>]`.
+    pub fn parse(kata: &str) -> Result<Self, Error> {
+        let invalid = |reason: &str| Error::InvalidKataRef {
+            kata: kata.to_string(),
+            reason: reason.to_string(),
+        };
+        let trimmed = kata.trim();
+        if trimmed.is_empty() {
+            return Err(invalid("it is empty"));
+        }
+
+        let without_scheme = trimmed
+            .strip_prefix("https://")
+            .or_else(|| trimmed.strip_prefix("http://"));
+        let is_url = without_scheme.is_some()
+            || trimmed.starts_with("www.codewars.com/")
+            || trimmed.starts_with("codewars.com/");
+        if !is_url {
+            if trimmed.contains(|c: char| c == '/' || c == '?' || c == '#' || c.is_whitespace()) {
+                return Err(invalid("an id or slug can't contain /, ?, # or spaces"));
+            }
+            return Ok(KataRef {
+                id_or_slug: trimmed.to_string(),
+                language: None,
+            });
+        }
+
+        let url = without_scheme.unwrap_or(trimmed);
+        let url = url.split(['?', '#']).next().unwrap_or("");
+        let mut parts = url.split('/');
+        let host = parts.next().unwrap_or("").to_lowercase();
+        if host != "codewars.com" && host != "www.codewars.com" {
+            return Err(invalid("not a Codewars URL"));
+        }
+        let segments: Vec<&str> = parts.filter(|segment| !segment.is_empty()).collect();
+        let id_or_slug = match segments.as_slice() {
+            ["kata", id_or_slug, ..] => {
+                percent_decode(id_or_slug).ok_or_else(|| invalid("invalid URL encoding"))?
+            }
+            _ => return Err(invalid("not a kata URL")),
+        };
+        if id_or_slug.trim().is_empty() {
+            return Err(invalid("it is empty"));
+        }
+        let language = match segments.as_slice() {
+            [_, _, "train", language, ..] => Some(language.to_lowercase()),
+            _ => None,
+        };
+        Ok(KataRef {
+            id_or_slug,
+            language,
+        })
+    }
+
+    /// Returns whether the reference is an id, which has 24 hexadecimal digits.
+    pub fn is_id(&self) -> bool {
+        self.id_or_slug.len() == 24 && self.id_or_slug.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// Returns id or slug encoded for use as a URL path segment.
+    pub fn path_segment(&self) -> String {
+        percent_encode(&self.id_or_slug)
+    }
+
+    /// Returns the kata page on Codewars.
+    pub fn url(&self) -> String {
+        format!("https://www.codewars.com/kata/{}", self.path_segment())
+    }
+}
+
+impl FromStr for KataRef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KataRef::parse(s)
+    }
+}
+
+impl fmt::Display for KataRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id_or_slug)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::KataRef;
+    use crate::err::Error;
+
+    #[test]
+    fn test_parse() {
+        let id = KataRef::parse("5277c8a221e209d3f6000b56").unwrap();
+        assert!(id.is_id());
+        assert_eq!(id.language, None);
+        let slug = KataRef::parse(" valid-braces ").unwrap();
+        assert!(!slug.is_id());
+        assert_eq!(slug.id_or_slug, "valid-braces");
+
+        let url =
+            KataRef::parse("https://www.codewars.com/kata/valid-braces/train/Rust?x=1").unwrap();
+        assert_eq!(url.id_or_slug, "valid-braces");
+        assert_eq!(url.language, Some("rust".to_string()));
+        let url = KataRef::parse("codewars.com/kata/5277c8a221e209d3f6000b56/").unwrap();
+        assert!(url.is_id());
+
+        let encoded = KataRef::parse("https://www.codewars.com/kata/caf%C3%A9%20kata").unwrap();
+        assert_eq!(encoded.id_or_slug, "café kata");
+        assert_eq!(encoded.path_segment(), "caf%C3%A9%20kata");
+        assert_eq!(
+            encoded.url(),
+            "https://www.codewars.com/kata/caf%C3%A9%20kata"
+        );
+
+        for invalid in &[
+            "",
+            "valid/braces",
+            "two words",
+            "https://example.com/kata/valid-braces",
+            "https://www.codewars.com/users/some_user",
+            "https://www.codewars.com/kata/bad%zz",
+        ] {
+            assert!(matches!(
+                KataRef::parse(invalid),
+                Err(Error::InvalidKataRef { .. })
+            ));
+        }
+    }
+}
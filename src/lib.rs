@@ -1,188 +1,518 @@
+pub mod badge;
+pub mod cache;
 pub mod code_challenges;
+pub mod colors;
+pub mod compare;
+pub mod description;
+pub mod disk_cache;
 pub mod err;
+mod escape;
+pub mod heatmap;
+pub mod hydrate;
+pub mod kata_ref;
+pub mod overlap;
+pub mod recommend;
+pub mod scaffold;
+pub mod search;
+pub mod snapshot;
+pub mod stats;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "store")]
+pub mod sync;
+pub mod table;
+pub mod team;
 pub mod user;
 pub mod user_challenges;
+pub mod watch;
+pub mod webhook;
 
 pub mod codewars {
 
+    use crate::cache::{CacheConfig, CacheStats, Endpoint, MemoryCache};
     use crate::code_challenges::CodeChallenge;
+    use crate::disk_cache::{CachedResponse, DiskCache};
     use crate::err::Error;
+    use crate::err::ReqwestError;
+    use crate::kata_ref::KataRef;
     use crate::user::User;
-    use crate::user_challenges::{AuthoredChallenge, CompletedChallenge};
+    use crate::user_challenges::{AuthoredChallenge, CompletedChallenge, CompletedChallengesPage};
+    use reqwest::blocking::{Client, Response};
+    use reqwest::header::{AUTHORIZATION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
     use reqwest::StatusCode;
     use serde_json::Value;
+    use snafu::ResultExt;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
     use std::vec::Vec;
 
-    /// A main structure that contains all the methods.
+    /// Base URL of the Codewars REST API.
+    pub const DEFAULT_BASE_URL: &str = "https://www.codewars.com/api/v1";
+
+    /// Whether the client may use the network.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Mode {
+        /// Requests go to Codewars unless they are answered by the in-memory cache.
+        Online,
+        /// Requests are answered only from the in-memory and disk caches.
+        Offline,
+    }
+
+    /// A main structure that contains all the methods. A non-empty `token` is sent in
+    /// `Authorization` header.
     pub struct Codewars {
         pub token: String,
+        pub base_url: String,
+        pub mode: Mode,
+        http: Client,
+        cache: Option<Mutex<MemoryCache>>,
+        disk_cache: Option<DiskCache>,
+        concurrency: usize,
+        rate_limit: Option<Duration>,
+        next_request: Mutex<Instant>,
+    }
+
+    /// Returns value of a response header if it is present and valid UTF-8.
+    fn header_value(response: &Response, name: reqwest::header::HeaderName) -> Option<String> {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
     }
 
     // Methods for Codewars struct
     impl Codewars {
         /// Returns a new instance of Codewars struct.
         pub fn new(token: String) -> Self {
-            Codewars { token }
+            Self::with_base_url(token, DEFAULT_BASE_URL.to_string())
         }
 
-        /// Retrieve a single user information from Codewars REST API. This method doesn't require use of token.
-        pub fn get_user(username: String) -> Result<User, Error> {
-            let url = format!("https://www.codewars.com/api/v1/users/{}", username);
+        /// Returns a new instance of Codewars struct that talks to given base URL instead of
+        /// Codewars, e.g. a local mock server.
+        pub fn with_base_url(token: String, base_url: String) -> Self {
+            Codewars {
+                token,
+                base_url: base_url.trim_end_matches('/').to_string(),
+                mode: Mode::Online,
+                http: Client::new(),
+                cache: None,
+                disk_cache: None,
+                concurrency: 4,
+                rate_limit: None,
+                next_request: Mutex::new(Instant::now()),
+            }
+        }
+
+        /// Returns the instance with an in-memory LRU cache of responses enabled.
+        pub fn with_memory_cache(mut self, config: CacheConfig) -> Self {
+            self.cache = Some(Mutex::new(MemoryCache::new(config)));
+            self
+        }
+
+        /// Returns the instance with a persistent cache of responses stored in `dir`.
+        /// Cached responses are revalidated with conditional requests, or refetched when
+        /// Codewars sent no `ETag` or `Last-Modified` for them.
+        pub fn with_disk_cache(mut self, dir: PathBuf) -> Self {
+            self.disk_cache = Some(DiskCache::new(dir));
+            self
+        }
+
+        /// Returns the instance switched to given mode. In `Mode::Offline` every request
+        /// that can't be answered from a cache fails with `Error::NotCached`.
+        pub fn with_mode(mut self, mode: Mode) -> Self {
+            self.mode = mode;
+            self
+        }
+
+        /// Returns the instance that runs at most `concurrency` requests at once in bulk
+        /// methods like `get_code_challenges`. Defaults to 4.
+        pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+            self.concurrency = concurrency.max(1);
+            self
+        }
+
+        /// Returns the instance that starts requests to Codewars at least `interval` apart,
+        /// also across threads. Responses from the caches aren't limited.
+        pub fn with_rate_limit(mut self, interval: Duration) -> Self {
+            self.rate_limit = Some(interval);
+            self
+        }
+
+        /// Returns hit/miss statistics of the in-memory cache, if it is enabled.
+        pub fn cache_stats(&self) -> Option<CacheStats> {
+            self.cache
+                .as_ref()
+                .map(|cache| cache.lock().unwrap().stats())
+        }
+
+        /// Removes cached profile, completed and authored challenges of a user from the
+        /// in-memory cache.
+        pub fn invalidate_user(&self, username: &str) {
+            if let Some(cache) = &self.cache {
+                cache
+                    .lock()
+                    .unwrap()
+                    .invalidate_path(&format!("/users/{}", username));
+            }
+        }
+
+        /// Removes a code challenge from the in-memory cache. `challenge_title` must refer to
+        /// the kata by the same id or slug that was used to retrieve it, directly or in a URL.
+        pub fn invalidate_code_challenge(&self, challenge_title: &str) {
+            if let (Some(cache), Ok(kata)) = (&self.cache, KataRef::parse(challenge_title)) {
+                cache
+                    .lock()
+                    .unwrap()
+                    .invalidate(&format!("/code-challenges/{}", kata.path_segment()));
+            }
+        }
+
+        /// Removes all responses from the in-memory cache.
+        pub fn clear_cache(&self) {
+            if let Some(cache) = &self.cache {
+                cache.lock().unwrap().clear();
+            }
+        }
+
+        /// Retrieves JSON from a path relative to base URL. Returns `None` if Codewars
+        /// responds with `404 Not Found`.
+        fn get_json(&self, endpoint: Endpoint, path: &str) -> Result<Option<Value>, Error> {
+            if let Some(cache) = &self.cache {
+                if let Some(value) = cache.lock().unwrap().get(path) {
+                    return Ok(Some(value));
+                }
+            }
+
+            let cached = self.disk_cache.as_ref().and_then(|disk| disk.get(path));
+            let url = format!("{}{}", self.base_url, path);
+            if self.mode == Mode::Offline {
+                let value = match cached {
+                    Some(cached) => cached.body,
+                    None => return Err(Error::NotCached { url }),
+                };
+                if let Some(cache) = &self.cache {
+                    cache.lock().unwrap().insert(endpoint, path, value.clone());
+                }
+                return Ok(Some(value));
+            }
+
+            let mut request = self.http.get(&url);
+            if !self.token.is_empty() {
+                request = request.header(AUTHORIZATION, self.token.as_str());
+            }
+            if let Some(cached) = &cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header(IF_NONE_MATCH, etag.as_str());
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+                }
+            }
+            self.wait_for_rate_limit();
+            let response = request.send().context(ReqwestError)?;
+
+            let value = match (response.status(), cached) {
+                (StatusCode::NOT_MODIFIED, Some(cached)) => {
+                    let value = cached.body.clone();
+                    // Refresh the time the response was last confirmed to be current
+                    self.store_on_disk(
+                        path,
+                        CachedResponse::new(
+                            cached.url,
+                            cached.etag,
+                            cached.last_modified,
+                            cached.body,
+                        ),
+                    );
+                    value
+                }
+                (status, _) if status.is_success() => {
+                    let etag = header_value(&response, ETAG);
+                    let last_modified = header_value(&response, LAST_MODIFIED);
+                    let value: Value = response.json().context(ReqwestError)?;
+                    // Stored even without validators, for offline mode; such responses are
+                    // refetched unconditionally
+                    self.store_on_disk(
+                        path,
+                        CachedResponse::new(url, etag, last_modified, value.clone()),
+                    );
+                    value
+                }
+                (StatusCode::NOT_FOUND, _) => return Ok(None),
+                _ => {
+                    return Err(Error::CodewarsError {
+                        message: "Error in retrieving data".to_string(),
+                    })
+                }
+            };
+
+            if let Some(cache) = &self.cache {
+                cache.lock().unwrap().insert(endpoint, path, value.clone());
+            }
+            Ok(Some(value))
+        }
 
-            // Call the URL
-            let user_result = reqwest::blocking::get(&url);
+        /// Sleeps until the next request may start, if requests are rate limited.
+        fn wait_for_rate_limit(&self) {
+            let interval = match self.rate_limit {
+                Some(interval) => interval,
+                None => return,
+            };
+            let wait = {
+                let mut next_request = self.next_request.lock().unwrap();
+                let now = Instant::now();
+                let start = (*next_request).max(now);
+                *next_request = start + interval;
+                start - now
+            };
+            if !wait.is_zero() {
+                thread::sleep(wait);
+            }
+        }
+
+        /// Calls `f` for every item on at most `concurrency` threads and returns the results
+        /// in the order of `items`. Requests made by `f` respect the rate limit.
+        pub(crate) fn fan_out<T, R, F>(&self, items: &[T], f: F) -> Vec<R>
+        where
+            T: Sync,
+            R: Send,
+            F: Fn(&T) -> R + Sync,
+        {
+            let next = AtomicUsize::new(0);
+            let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+                let handles: Vec<_> = (0..self.concurrency.min(items.len()))
+                    .map(|_| {
+                        scope.spawn(|| {
+                            let mut results = vec![];
+                            loop {
+                                let n = next.fetch_add(1, Ordering::SeqCst);
+                                if n >= items.len() {
+                                    break;
+                                }
+                                results.push((n, f(&items[n])));
+                            }
+                            results
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("Request thread panicked"))
+                    .collect()
+            });
+            results.sort_by_key(|(n, _)| *n);
+            results.into_iter().map(|(_, result)| result).collect()
+        }
+
+        /// Stores a response in the disk cache, if it is enabled. Failing to write the cache
+        /// doesn't fail the request.
+        fn store_on_disk(&self, path: &str, response: CachedResponse) {
+            if let Some(disk) = &self.disk_cache {
+                let _ = disk.store(path, &response);
+            }
+        }
 
-            // Process the result
-            match user_result {
-                Ok(response) => {
+        /// Retrieve a single user information from Codewars REST API. This method doesn't require use of token.
+        pub fn get_user(&self, username: String) -> Result<User, Error> {
+            let path = format!("/users/{}", username);
+            match self.get_json(Endpoint::User, &path)? {
+                Some(user_json) => {
                     let mut my_user = User::new();
-                    // We create User instance only if user details successfully retrieved
-                    if response.status().is_success() {
-                        let user_json: Value = response.json().unwrap();
-                        my_user.from_json(&user_json);
-                        Ok(my_user)
-                    } else {
-                        match response.status() {
-                            StatusCode::NOT_FOUND => Err(Error::UserNotFound { username }),
-                            _ => Err(Error::CodewarsError {
-                                message: "Error in retrieving data".to_string(),
-                            }),
-                        }
-                    }
+                    my_user.from_json(&user_json);
+                    Ok(my_user)
                 }
-                Err(e) => Err(Error::ReqwestError { source: e }),
+                None => Err(Error::UserNotFound { username }),
+            }
+        }
+
+        /// Retrieves a single page of completed challenges, most recent first. Pages are
+        /// numbered from 0.
+        pub fn get_completed_challenges_page(
+            &self,
+            username: String,
+            page: u64,
+        ) -> Result<CompletedChallengesPage, Error> {
+            let path = format!(
+                "/users/{}/code-challenges/completed?page={}",
+                username, page
+            );
+            match self.get_json(Endpoint::CompletedChallenges, &path)? {
+                Some(json_data) => {
+                    let mut completed_page = CompletedChallengesPage::new();
+                    completed_page.from_json(&json_data);
+                    Ok(completed_page)
+                }
+                None => Err(Error::UserNotFound { username }),
             }
         }
 
         /// Retrieves all the completed challenges.
         pub fn get_completed_challenges(
+            &self,
             username: String,
         ) -> Result<Vec<CompletedChallenge>, Error> {
             let mut current_page = 0;
             let mut total_pages: Option<u64> = None;
             let mut completed_challenges: Vec<CompletedChallenge> = Vec::new();
             loop {
-                if total_pages.is_some() && current_page >= total_pages.unwrap() {
-                    break;
-                }
-                let url = format!(
-                    "https://www.codewars.com/api/v1/users/{}/code-challenges/completed?page={}",
-                    username, current_page
-                );
-                let result = reqwest::blocking::get(&url);
-                match result {
-                    Ok(response) => {
-                        if response.status().is_success() {
-                            let json_data: Value = response.json().unwrap();
-                            let total_pages_received: u64 =
-                                json_data.get("totalPages").unwrap().as_u64().unwrap();
-                            let data = json_data.get("data").unwrap().as_array().unwrap();
-                            if total_pages.is_none() {
-                                total_pages = Some(total_pages_received);
-                            }
-                            for d in data {
-                                let mut completed_challenge = CompletedChallenge::new();
-                                completed_challenge.from_json(d);
-                                completed_challenges.push(completed_challenge);
-                            }
-                            current_page += 1;
-                        // Ok(completed_challenges)
-                        } else {
-                            match response.status() {
-                                StatusCode::NOT_FOUND => {
-                                    return Err(Error::UserNotFound { username })
-                                }
-                                _ => {
-                                    return Err(Error::CodewarsError {
-                                        message: "Error in retrieving data".to_string(),
-                                    })
-                                }
-                            }
-                        }
+                if let Some(total_pages) = total_pages {
+                    if current_page >= total_pages {
+                        break;
                     }
-                    Err(e) => return Err(Error::ReqwestError { source: e }),
                 }
-                // current_page += 1;
+                let completed_page =
+                    self.get_completed_challenges_page(username.clone(), current_page)?;
+                if total_pages.is_none() {
+                    total_pages = Some(completed_page.total_pages);
+                }
+                completed_challenges.extend(completed_page.data);
+                current_page += 1;
             }
             Ok(completed_challenges)
         }
 
         /// Returns all the authored challenges.
-        pub fn get_authored_challenges(username: String) -> Result<Vec<AuthoredChallenge>, Error> {
+        pub fn get_authored_challenges(
+            &self,
+            username: String,
+        ) -> Result<Vec<AuthoredChallenge>, Error> {
             let mut authored_challenges: Vec<AuthoredChallenge> = Vec::new();
-            let url = format!(
-                "https://www.codewars.com/api/v1/users/{}/code-challenges/authored",
-                username
-            );
-            let result = reqwest::blocking::get(&url);
-            match result {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        let json_data: Value = response.json().unwrap();
-                        let authored_challenges_received =
-                            json_data.get("data").unwrap().as_array().unwrap();
-                        for authored_challenge_received in authored_challenges_received {
-                            let mut authored_challenge = AuthoredChallenge::new();
-                            authored_challenge.from_json(authored_challenge_received);
-                            authored_challenges.push(authored_challenge);
-                        }
-                        Ok(authored_challenges)
-                    } else {
-                        match response.status() {
-                            StatusCode::NOT_FOUND => Err(Error::UserNotFound { username }),
-                            _ => Err(Error::CodewarsError {
-                                message: "Error in retrieving data".to_string(),
-                            }),
-                        }
-                    }
-                }
-                Err(e) => Err(Error::ReqwestError { source: e }),
+            let path = format!("/users/{}/code-challenges/authored", username);
+            let json_data = match self.get_json(Endpoint::AuthoredChallenges, &path)? {
+                Some(json_data) => json_data,
+                None => return Err(Error::UserNotFound { username }),
+            };
+            let authored_challenges_received = json_data.get("data").unwrap().as_array().unwrap();
+            for authored_challenge_received in authored_challenges_received {
+                let mut authored_challenge = AuthoredChallenge::new();
+                authored_challenge.from_json(authored_challenge_received);
+                authored_challenges.push(authored_challenge);
             }
+            Ok(authored_challenges)
+        }
+
+        /// Returns a single code challenge detail. `challenge_title` is an id, a slug or a kata
+        /// URL, see `KataRef::parse`.
+        pub fn get_code_challenge(&self, challenge_title: String) -> Result<CodeChallenge, Error> {
+            self.get_code_challenge_by_ref(&KataRef::parse(&challenge_title)?)
         }
 
         /// Returns a single code challenge detail.
-        pub fn get_code_challenge(challenge_title: String) -> Result<CodeChallenge, Error> {
-            let url = format!(
-                "https://www.codewars.com/api/v1/code-challenges/{}",
-                challenge_title
-            );
-            let result = reqwest::blocking::get(&url);
-
-            match result {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        let mut code_challenge = CodeChallenge::new();
-                        let response_json: Value = response.json().unwrap();
-                        code_challenge.from_json(&response_json);
-
-                        Ok(code_challenge)
-                    } else {
-                        match response.status() {
-                            StatusCode::NOT_FOUND => {
-                                Err(Error::ChallengeNotFound { challenge_title })
-                            }
-                            _ => Err(Error::CodewarsError {
-                                message: "Error in retrieving data".to_string(),
-                            }),
-                        }
-                    }
+        pub fn get_code_challenge_by_ref(&self, kata: &KataRef) -> Result<CodeChallenge, Error> {
+            let path = format!("/code-challenges/{}", kata.path_segment());
+            match self.get_json(Endpoint::CodeChallenge, &path)? {
+                Some(response_json) => {
+                    let mut code_challenge = CodeChallenge::new();
+                    code_challenge.from_json(&response_json);
+                    Ok(code_challenge)
                 }
-                Err(e) => Err(Error::ReqwestError { source: e }),
+                None => Err(Error::ChallengeNotFound {
+                    challenge_title: kata.to_string(),
+                }),
             }
         }
+
+        /// Returns details of many code challenges by id, slug or URL, each with its own
+        /// result, so a missing challenge doesn't stop the others. References to the same
+        /// id or slug are fetched once and returned once, in the order they first appear.
+        /// An id and a slug of the same kata can't be told apart without fetching, so both
+        /// are fetched. Requests run in parallel up to the configured concurrency and
+        /// respect the rate limit and caches.
+        pub fn get_code_challenges(
+            &self,
+            ids: &[&str],
+        ) -> Vec<(String, Result<CodeChallenge, Error>)> {
+            let mut seen = HashSet::new();
+            let unique: Vec<&str> = ids
+                .iter()
+                .cloned()
+                .filter(|id| {
+                    let key = KataRef::parse(id)
+                        .map(|kata| kata.id_or_slug)
+                        .unwrap_or_else(|_| id.to_string());
+                    seen.insert(key)
+                })
+                .collect();
+
+            self.fan_out(&unique, |id| {
+                (id.to_string(), self.get_code_challenge(id.to_string()))
+            })
+        }
+    }
+
+    /// Retrieves a single user information with a default client. Kept for code written
+    /// against the static `Codewars::get_user` of earlier versions.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `Codewars::new(token).get_user(username)`"
+    )]
+    pub fn get_user(username: String) -> Result<User, Error> {
+        Codewars::new(String::new()).get_user(username)
+    }
+
+    /// Retrieves all the completed challenges with a default client. Kept for code written
+    /// against the static `Codewars::get_completed_challenges` of earlier versions.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `Codewars::new(token).get_completed_challenges(username)`"
+    )]
+    pub fn get_completed_challenges(username: String) -> Result<Vec<CompletedChallenge>, Error> {
+        Codewars::new(String::new()).get_completed_challenges(username)
+    }
+
+    /// Returns all the authored challenges with a default client. Kept for code written
+    /// against the static `Codewars::get_authored_challenges` of earlier versions.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `Codewars::new(token).get_authored_challenges(username)`"
+    )]
+    pub fn get_authored_challenges(username: String) -> Result<Vec<AuthoredChallenge>, Error> {
+        Codewars::new(String::new()).get_authored_challenges(username)
+    }
+
+    /// Returns a single code challenge detail with a default client. Kept for code written
+    /// against the static `Codewars::get_code_challenge` of earlier versions.
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `Codewars::new(token).get_code_challenge(challenge_title)`"
+    )]
+    pub fn get_code_challenge(challenge_title: String) -> Result<CodeChallenge, Error> {
+        Codewars::new(String::new()).get_code_challenge(challenge_title)
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::codewars::Codewars;
+    use crate::cache::{CacheConfig, Endpoint};
+    use crate::codewars::{Codewars, Mode};
+    use crate::err::Error;
+    use codewars_mock::{Fault, MockServer, MockServerBuilder};
+    use std::path::Path;
+    use std::time::{Duration, Instant};
     // use crate::user::User;
 
+    /// Returns a mock server builder filled up with data from `tests/data`.
+    fn mock_builder() -> MockServerBuilder {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        MockServerBuilder::from_dir(&data_dir).unwrap()
+    }
+
+    fn mock_client(server: &MockServer) -> Codewars {
+        Codewars::with_base_url("".to_string(), server.base_url())
+    }
+
     #[test]
     fn test_get_struct() {
         // Call a function
-        let user = Codewars::get_user("vbmade2000".to_string()).unwrap();
+        let user = Codewars::new("".to_string())
+            .get_user("vbmade2000".to_string())
+            .unwrap();
 
         // Assert values
         assert_eq!(user.name, "Malhar Vora".to_string());
@@ -191,19 +521,22 @@ mod tests {
 
     #[test]
     fn test_get_completed_challenges() {
-        let _completed_challenges = Codewars::get_completed_challenges("hobovsky".to_string());
+        let _completed_challenges =
+            Codewars::new("".to_string()).get_completed_challenges("hobovsky".to_string());
         // assert_eq!(_completed_challenges.unwrap().len(), 878);
     }
 
     #[test]
     fn test_get_authored_challenges() {
-        let _authored_challenges = Codewars::get_authored_challenges("hobovsky".to_string());
+        let _authored_challenges =
+            Codewars::new("".to_string()).get_authored_challenges("hobovsky".to_string());
         // assert_eq!(_authoered_challenges.unwrap().len(), 878);
     }
 
     #[test]
     fn test_get_code_challenge() {
-        let _code_challenge = Codewars::get_code_challenge("valid-braces".to_string());
+        let _code_challenge =
+            Codewars::new("".to_string()).get_code_challenge("valid-braces".to_string());
         // assert_eq!(_code_challenge.unwrap().rank.id, -6);
         // assert_eq!(_code_challenge.unwrap().unresolved.issues, 12);
         assert_eq!(
@@ -211,4 +544,186 @@ mod tests {
             "xDranik".to_string()
         );
     }
+
+    #[test]
+    fn test_mock_get_user() {
+        let server = mock_builder().start().unwrap();
+        let codewars = mock_client(&server);
+
+        let user = codewars.get_user("some_user".to_string()).unwrap();
+        assert_eq!(user.name, "Some Person".to_string());
+        assert_eq!(user.ranks.languages.len(), 3);
+
+        let missing = codewars.get_user("nobody".to_string());
+        assert!(matches!(missing, Err(Error::UserNotFound { .. })));
+    }
+
+    #[test]
+    fn test_mock_get_completed_challenges_paginated() {
+        let server = mock_builder().page_size(2).start().unwrap();
+        let codewars = mock_client(&server);
+
+        let completed = codewars
+            .get_completed_challenges("some_user".to_string())
+            .unwrap();
+        assert_eq!(completed.len(), 3);
+        assert_eq!(completed[2].slug, "valid-braces".to_string());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_mock_get_authored_and_code_challenge() {
+        let server = mock_builder().start().unwrap();
+        let codewars = mock_client(&server);
+
+        let authored = codewars
+            .get_authored_challenges("some_user".to_string())
+            .unwrap();
+        assert_eq!(authored[0].rank_name, "8 kyu".to_string());
+
+        let by_slug = codewars
+            .get_code_challenge("valid-braces".to_string())
+            .unwrap();
+        let by_id = codewars
+            .get_code_challenge("5277c8a221e209d3f6000b56".to_string())
+            .unwrap();
+        assert_eq!(by_slug.id, by_id.id);
+        assert_eq!(by_slug.created_by.username, "xDranik".to_string());
+    }
+
+    #[test]
+    fn test_mock_get_code_challenges() {
+        let server = mock_builder().start().unwrap();
+        let codewars = mock_client(&server)
+            .with_concurrency(2)
+            .with_rate_limit(Duration::from_millis(50))
+            .with_memory_cache(CacheConfig::new());
+
+        let started = Instant::now();
+        let results = codewars.get_code_challenges(&[
+            "valid-braces",
+            "missing",
+            "https://www.codewars.com/kata/valid-braces/train/rust",
+            "5277c8a221e209d3f6000b56",
+        ]);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        let ids: Vec<&str> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["valid-braces", "missing", "5277c8a221e209d3f6000b56"]
+        );
+        assert_eq!(results[0].1.as_ref().unwrap().name, "Valid Braces");
+        assert!(matches!(results[1].1, Err(Error::ChallengeNotFound { .. })));
+        assert!(results[2].1.is_ok());
+        assert_eq!(server.requests().len(), 3);
+
+        // Cached challenges are neither requested nor rate limited
+        let started = Instant::now();
+        codewars.get_code_challenges(&["valid-braces", "5277c8a221e209d3f6000b56"]);
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_mock_faults() {
+        let server = mock_builder()
+            .fault_times("/users/some_user", Fault::InternalServerError, 1)
+            .fault("/code-challenges", Fault::MalformedJson)
+            .start()
+            .unwrap();
+        let codewars = mock_client(&server);
+
+        let failed = codewars.get_user("some_user".to_string());
+        assert!(matches!(failed, Err(Error::CodewarsError { .. })));
+        assert!(codewars.get_user("some_user".to_string()).is_ok());
+
+        let malformed = codewars.get_code_challenge("valid-braces".to_string());
+        assert!(matches!(malformed, Err(Error::ReqwestError { .. })));
+    }
+
+    #[test]
+    fn test_memory_cache() {
+        let server = mock_builder().start().unwrap();
+        let codewars = mock_client(&server).with_memory_cache(
+            CacheConfig::new().with_ttl(Endpoint::CodeChallenge, Duration::from_secs(0)),
+        );
+
+        codewars.get_user("some_user".to_string()).unwrap();
+        codewars.get_user("some_user".to_string()).unwrap();
+        assert_eq!(server.requests().len(), 1);
+
+        // Code challenges are configured not to be cached
+        codewars
+            .get_code_challenge("valid-braces".to_string())
+            .unwrap();
+        codewars
+            .get_code_challenge("valid-braces".to_string())
+            .unwrap();
+        assert_eq!(server.requests().len(), 3);
+
+        codewars.invalidate_user("some_user");
+        codewars.get_user("some_user".to_string()).unwrap();
+        assert_eq!(server.requests().len(), 4);
+
+        let stats = codewars.cache_stats().unwrap();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 4);
+    }
+
+    #[test]
+    fn test_disk_cache_conditional_requests() {
+        let server = mock_builder().start().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+
+        let codewars = mock_client(&server).with_disk_cache(cache_dir.path().to_path_buf());
+        let first = codewars
+            .get_code_challenge("valid-braces".to_string())
+            .unwrap();
+
+        // A new client, as after a restart, revalidates the stored response
+        let codewars = mock_client(&server).with_disk_cache(cache_dir.path().to_path_buf());
+        let second = codewars
+            .get_code_challenge("valid-braces".to_string())
+            .unwrap();
+
+        assert_eq!(first.description, second.description);
+        assert_eq!(server.statuses(), vec![200, 304]);
+    }
+
+    #[test]
+    fn test_disk_cache_without_validators() {
+        let server = mock_builder().etags(false).start().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+
+        let codewars = mock_client(&server).with_disk_cache(cache_dir.path().to_path_buf());
+        codewars.get_user("some_user".to_string()).unwrap();
+        codewars.get_user("some_user".to_string()).unwrap();
+        assert_eq!(server.statuses(), vec![200, 200]);
+
+        let codewars = mock_client(&server)
+            .with_disk_cache(cache_dir.path().to_path_buf())
+            .with_mode(Mode::Offline);
+        let user = codewars.get_user("some_user".to_string()).unwrap();
+        assert_eq!(user.username, "some_user".to_string());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_offline_mode() {
+        let server = mock_builder().start().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+
+        let codewars = mock_client(&server).with_disk_cache(cache_dir.path().to_path_buf());
+        codewars.get_user("some_user".to_string()).unwrap();
+
+        let codewars = mock_client(&server)
+            .with_disk_cache(cache_dir.path().to_path_buf())
+            .with_mode(Mode::Offline);
+        let user = codewars.get_user("some_user".to_string()).unwrap();
+        assert_eq!(user.username, "some_user".to_string());
+
+        let not_cached = codewars.get_code_challenge("valid-braces".to_string());
+        assert!(matches!(not_cached, Err(Error::NotCached { .. })));
+        assert_eq!(server.requests().len(), 1);
+    }
 }
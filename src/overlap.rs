@@ -0,0 +1,261 @@
+//! Overlap of completed challenges between users.
+//!
+//! Shows which katas a group has in common, which only one member has solved, how
+//! similar the histories of two members are and which katas teammates have solved that
+//! a member hasn't, most popular first.
+
+use crate::codewars::Codewars;
+use crate::err::Error;
+use crate::user_challenges::CompletedChallenge;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A structure that holds a kata and the users who have completed it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SolvedKata {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    /// Usernames in the order users were given.
+    pub solvers: Vec<String>,
+}
+
+/// A structure that holds the similarity of completed challenges of two users.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Similarity {
+    pub first: String,
+    pub second: String,
+    /// Number of katas both users have completed.
+    pub common: usize,
+    /// Common katas divided by katas completed by either user, from 0 to 1.
+    pub jaccard: f64,
+}
+
+/// A structure that holds completed challenges of a group of users.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Overlap {
+    pub usernames: Vec<String>,
+    /// Katas completed by at least one user, by id.
+    pub katas: BTreeMap<String, SolvedKata>,
+}
+
+impl Overlap {
+    /// Returns overlap of users with their completed challenges. A user given more than
+    /// once counts once.
+    pub fn new(completed: &[(String, Vec<CompletedChallenge>)]) -> Self {
+        let mut overlap = Overlap::default();
+        for (username, challenges) in completed {
+            if !overlap.usernames.contains(username) {
+                overlap.usernames.push(username.clone());
+            }
+            for challenge in challenges {
+                let kata = overlap
+                    .katas
+                    .entry(challenge.id.clone())
+                    .or_insert_with(|| SolvedKata {
+                        id: challenge.id.clone(),
+                        name: challenge.name.clone(),
+                        slug: challenge.slug.clone(),
+                        solvers: vec![],
+                    });
+                if !kata.solvers.contains(username) {
+                    kata.solvers.push(username.clone());
+                }
+            }
+        }
+        overlap
+    }
+
+    /// Fetches completed challenges of users in parallel, up to the concurrency and within
+    /// the rate limit of `codewars`. Every user is fetched once. Users whose challenges
+    /// couldn't be fetched are left out and returned with the reason.
+    pub fn fetch(codewars: &Codewars, usernames: &[String]) -> (Self, Vec<(String, Error)>) {
+        let mut unique: Vec<&String> = vec![];
+        for username in usernames {
+            if !unique.contains(&username) {
+                unique.push(username);
+            }
+        }
+        let results = codewars.fan_out(&unique, |username| {
+            (
+                username.to_string(),
+                codewars.get_completed_challenges(username.to_string()),
+            )
+        });
+        let mut completed = vec![];
+        let mut errors = vec![];
+        for (username, result) in results {
+            match result {
+                Ok(challenges) => completed.push((username, challenges)),
+                Err(e) => errors.push((username, e)),
+            }
+        }
+        (Overlap::new(&completed), errors)
+    }
+
+    /// Returns katas completed by every user, by name.
+    pub fn common(&self) -> Vec<&SolvedKata> {
+        let mut common: Vec<&SolvedKata> = self
+            .katas
+            .values()
+            .filter(|kata| kata.solvers.len() == self.usernames.len())
+            .collect();
+        common.sort_by(|a, b| a.name.cmp(&b.name));
+        common
+    }
+
+    /// Returns katas completed by only one user, by name, for every user.
+    pub fn unique(&self) -> BTreeMap<String, Vec<&SolvedKata>> {
+        let mut unique: BTreeMap<String, Vec<&SolvedKata>> = self
+            .usernames
+            .iter()
+            .map(|username| (username.clone(), vec![]))
+            .collect();
+        for kata in self.katas.values().filter(|kata| kata.solvers.len() == 1) {
+            if let Some(katas) = unique.get_mut(&kata.solvers[0]) {
+                katas.push(kata);
+            }
+        }
+        for katas in unique.values_mut() {
+            katas.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        unique
+    }
+
+    fn solved_by(&self, username: &str) -> BTreeSet<&String> {
+        self.katas
+            .values()
+            .filter(|kata| kata.solvers.iter().any(|solver| solver == username))
+            .map(|kata| &kata.id)
+            .collect()
+    }
+
+    /// Returns similarity of two users, `None` if either isn't part of the overlap.
+    /// Users without completed challenges have similarity 0.
+    pub fn similarity(&self, first: &str, second: &str) -> Option<Similarity> {
+        if !self.usernames.iter().any(|username| username == first)
+            || !self.usernames.iter().any(|username| username == second)
+        {
+            return None;
+        }
+        let first_solved = self.solved_by(first);
+        let second_solved = self.solved_by(second);
+        let common = first_solved.intersection(&second_solved).count();
+        let either = first_solved.union(&second_solved).count();
+        Some(Similarity {
+            first: first.to_string(),
+            second: second.to_string(),
+            common,
+            jaccard: if either == 0 {
+                0.0
+            } else {
+                common as f64 / either as f64
+            },
+        })
+    }
+
+    /// Returns similarity of every pair of users, most similar first.
+    pub fn similarities(&self) -> Vec<Similarity> {
+        let mut similarities = vec![];
+        for (n, first) in self.usernames.iter().enumerate() {
+            for second in &self.usernames[n + 1..] {
+                similarities.extend(self.similarity(first, second));
+            }
+        }
+        similarities.sort_by(|a, b| {
+            b.jaccard
+                .partial_cmp(&a.jaccard)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.common.cmp(&a.common))
+        });
+        similarities
+    }
+
+    /// Returns katas other users have completed and `username` hasn't, completed by most
+    /// teammates first, then by name. Returns `None` if the user isn't part of the
+    /// overlap.
+    pub fn recommendations(&self, username: &str) -> Option<Vec<&SolvedKata>> {
+        if !self.usernames.iter().any(|known| known == username) {
+            return None;
+        }
+        let mut recommendations: Vec<&SolvedKata> = self
+            .katas
+            .values()
+            .filter(|kata| !kata.solvers.iter().any(|solver| solver == username))
+            .collect();
+        recommendations.sort_by(|a, b| {
+            b.solvers
+                .len()
+                .cmp(&a.solvers.len())
+                .then(a.name.cmp(&b.name))
+        });
+        Some(recommendations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Overlap;
+    use crate::user_challenges::CompletedChallenge;
+
+    fn completed(username: &str, ids: &[&str]) -> (String, Vec<CompletedChallenge>) {
+        let challenges = ids
+            .iter()
+            .map(|id| {
+                let mut challenge = CompletedChallenge::new();
+                challenge.id = id.to_string();
+                challenge.name = format!("Kata {}", id);
+                challenge
+            })
+            .collect();
+        (username.to_string(), challenges)
+    }
+
+    #[test]
+    fn test_overlap() {
+        let overlap = Overlap::new(&[
+            completed("alice", &["a", "b", "c", "d"]),
+            completed("bob", &["a", "b", "e"]),
+            completed("carol", &["a", "c", "e", "f"]),
+        ]);
+
+        let common: Vec<&str> = overlap.common().iter().map(|k| k.id.as_str()).collect();
+        assert_eq!(common, vec!["a"]);
+        let unique = overlap.unique();
+        assert_eq!(unique["alice"][0].id, "d");
+        assert!(unique["bob"].is_empty());
+        assert_eq!(unique["carol"][0].id, "f");
+
+        let similarity = overlap.similarity("alice", "bob").unwrap();
+        assert_eq!(similarity.common, 2);
+        assert!((similarity.jaccard - 0.4).abs() < 1e-9);
+        assert!(overlap.similarity("alice", "dave").is_none());
+        let similarities = overlap.similarities();
+        assert_eq!(similarities.len(), 3);
+        assert_eq!(similarities[2].first, "alice");
+        assert_eq!(similarities[2].second, "carol");
+
+        let recommendations: Vec<&str> = overlap
+            .recommendations("bob")
+            .unwrap()
+            .iter()
+            .map(|k| k.id.as_str())
+            .collect();
+        assert_eq!(recommendations, vec!["c", "d", "f"]);
+        assert!(overlap.recommendations("dave").is_none());
+    }
+
+    #[test]
+    fn test_overlap_counts_repeated_users_once() {
+        let overlap = Overlap::new(&[
+            completed("alice", &["a", "b"]),
+            completed("alice", &["a", "b"]),
+            completed("bob", &["a"]),
+        ]);
+        assert_eq!(overlap.usernames, vec!["alice", "bob"]);
+        let common: Vec<&str> = overlap.common().iter().map(|k| k.id.as_str()).collect();
+        assert_eq!(common, vec!["a"]);
+        assert_eq!(overlap.similarities().len(), 1);
+    }
+}
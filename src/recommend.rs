@@ -0,0 +1,431 @@
+//! Recommendations of code challenges to solve next.
+//!
+//! Candidates are known katas available in a language that the user hasn't completed,
+//! ranked at the user's rank in the language or up to a few ranks above. Katas the user
+//! has completed only in other languages can be allowed as well. Unranked katas, e.g.
+//! beta ones, are never recommended. Each
+//! candidate gets a score from its stars, votes, how many attempts end in a completion,
+//! how well its tags match katas the user already completed and how close its rank is.
+//! Every recommendation keeps the contribution of each factor, so it can be explained.
+
+use crate::code_challenges::{CodeChallenge, Rank};
+#[cfg(feature = "store")]
+use crate::err::Error;
+#[cfg(feature = "store")]
+use crate::store::Store;
+use crate::user::User;
+use crate::user_challenges::CompletedChallenge;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Rank id of 8 kyu, the lowest rank.
+const LOWEST_RANK: i64 = -8;
+
+/// A structure that holds weights of recommendation factors.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Weights {
+    pub stars: f64,
+    pub votes: f64,
+    pub satisfaction: f64,
+    pub tags: f64,
+    pub rank: f64,
+}
+
+impl Weights {
+    /// Returns weights that favour tag affinity and well received katas.
+    pub fn new() -> Self {
+        Weights {
+            stars: 1.0,
+            votes: 1.0,
+            satisfaction: 1.0,
+            tags: 1.5,
+            rank: 1.0,
+        }
+    }
+}
+
+impl Default for Weights {
+    /// Returns a new instance of Weights struct with default values.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a part of a score comes from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Factor {
+    Stars,
+    Votes,
+    Satisfaction,
+    Tags,
+    Rank,
+}
+
+/// A structure that holds how much a factor contributed to a score and why.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Reason {
+    pub factor: Factor,
+    /// Weighted value of the factor.
+    pub contribution: f64,
+    pub detail: String,
+}
+
+/// A structure that holds a recommended kata.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    pub challenge: CodeChallenge,
+    pub language: String,
+    pub score: f64,
+    /// Reasons from the largest contribution.
+    pub reasons: Vec<Reason>,
+}
+
+impl Recommendation {
+    /// Returns reasons as one line, e.g. `tagged Algorithms (12 completed), 4 kyu as
+    /// your rank`.
+    pub fn explain(&self) -> String {
+        self.reasons
+            .iter()
+            .filter(|reason| reason.contribution > 0.0)
+            .map(|reason| reason.detail.clone())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// Returns position of a rank from 0 for 8 kyu. Dan ranks follow 1 kyu without a gap.
+fn level(rank: i64) -> i64 {
+    if rank < 0 {
+        rank - LOWEST_RANK
+    } else {
+        rank - LOWEST_RANK - 1
+    }
+}
+
+/// Returns `log(1 + value)` relative to the largest value, from 0 to 1.
+fn log_scale(value: u64, max: u64) -> f64 {
+    if max == 0 {
+        0.0
+    } else {
+        (value as f64).ln_1p() / (max as f64).ln_1p()
+    }
+}
+
+/// Suggests unsolved katas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommender {
+    stretch: i64,
+    weights: Weights,
+    limit: usize,
+    other_languages: bool,
+}
+
+impl Recommender {
+    /// Returns a recommender of 10 katas at most one rank above the user.
+    pub fn new() -> Self {
+        Recommender {
+            stretch: 1,
+            weights: Weights::new(),
+            limit: 10,
+            other_languages: false,
+        }
+    }
+
+    /// Sets how many ranks above the user's rank candidates may be.
+    pub fn with_stretch(mut self, stretch: i64) -> Self {
+        self.stretch = stretch.max(0);
+        self
+    }
+
+    /// Sets weights of the factors.
+    pub fn with_weights(mut self, weights: Weights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Sets the maximum number of recommendations.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Sets whether katas completed only in other languages may be recommended, to be
+    /// solved again in the language.
+    pub fn with_other_languages(mut self, other_languages: bool) -> Self {
+        self.other_languages = other_languages;
+        self
+    }
+
+    /// Returns katas from `challenges` recommended to a user in a language, best first.
+    ///
+    /// Challenges serve both as candidates and as details of completed katas, which tag
+    /// affinity is computed from. Users without a rank in the language are treated as
+    /// 8 kyu.
+    pub fn recommend(
+        &self,
+        user: &User,
+        completed: &[CompletedChallenge],
+        challenges: &[CodeChallenge],
+        language: &str,
+    ) -> Vec<Recommendation> {
+        let user_rank = user
+            .ranks
+            .languages
+            .get(language)
+            .map(|rank| rank.rank)
+            .unwrap_or(LOWEST_RANK);
+        let user_level = level(user_rank);
+
+        let completed_ids: HashSet<&String> = completed.iter().map(|c| &c.id).collect();
+        let solved: HashSet<&String> = completed
+            .iter()
+            .filter(|c| {
+                !self.other_languages || c.completed_languages.iter().any(|l| l == language)
+            })
+            .map(|c| &c.id)
+            .collect();
+        let mut tag_counts: HashMap<&String, usize> = HashMap::new();
+        for challenge in challenges
+            .iter()
+            .filter(|challenge| completed_ids.contains(&challenge.id))
+        {
+            for tag in &challenge.tags {
+                *tag_counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+        let max_tag_count = tag_counts.values().cloned().max().unwrap_or(0);
+
+        let candidates: Vec<&CodeChallenge> = challenges
+            .iter()
+            .filter(|challenge| !solved.contains(&challenge.id))
+            .filter(|challenge| challenge.languages.iter().any(|l| l == language))
+            // Rank id 0 means the kata has no rank yet
+            .filter(|challenge| challenge.rank.id != 0)
+            .filter(|challenge| {
+                let distance = level(challenge.rank.id) - user_level;
+                distance >= 0 && distance <= self.stretch
+            })
+            .collect();
+        let max_stars = candidates.iter().map(|c| c.total_stars).max().unwrap_or(0);
+
+        let mut recommendations: Vec<Recommendation> = candidates
+            .into_iter()
+            .map(|challenge| {
+                let mut reasons = vec![
+                    Reason {
+                        factor: Factor::Stars,
+                        contribution: self.weights.stars
+                            * log_scale(challenge.total_stars, max_stars),
+                        detail: format!("{} stars", challenge.total_stars),
+                    },
+                    Reason {
+                        factor: Factor::Votes,
+                        contribution: self.weights.votes * challenge.normalized_vote_score(),
+                        detail: format!(
+                            "vote score {} from {} completions",
+                            challenge.vote_score, challenge.total_completed
+                        ),
+                    },
+                    self.satisfaction(challenge),
+                    self.tag_affinity(challenge, &tag_counts, max_tag_count),
+                    self.rank_fit(&challenge.rank, user_level),
+                ];
+                reasons.sort_by(|a, b| {
+                    b.contribution
+                        .partial_cmp(&a.contribution)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+                Recommendation {
+                    challenge: challenge.clone(),
+                    language: language.to_string(),
+                    score: reasons.iter().map(|reason| reason.contribution).sum(),
+                    reasons,
+                }
+            })
+            .collect();
+        recommendations.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.challenge.name.cmp(&b.challenge.name))
+        });
+        recommendations.truncate(self.limit);
+        recommendations
+    }
+
+    /// Share of attempts that end in a completion.
+    fn satisfaction(&self, challenge: &CodeChallenge) -> Reason {
+        let ratio = challenge.completion_rate();
+        Reason {
+            factor: Factor::Satisfaction,
+            contribution: self.weights.satisfaction * ratio,
+            detail: format!("{:.0}% of attempts completed", ratio * 100.0),
+        }
+    }
+
+    /// Affinity of the most completed tag of the challenge.
+    fn tag_affinity(
+        &self,
+        challenge: &CodeChallenge,
+        tag_counts: &HashMap<&String, usize>,
+        max_tag_count: usize,
+    ) -> Reason {
+        let best = challenge
+            .tags
+            .iter()
+            .map(|tag| (tag, tag_counts.get(tag).cloned().unwrap_or(0)))
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)));
+        match best {
+            Some((tag, count)) if count > 0 => Reason {
+                factor: Factor::Tags,
+                contribution: self.weights.tags * count as f64 / max_tag_count as f64,
+                detail: format!("tagged {} ({} completed)", tag, count),
+            },
+            _ => Reason {
+                factor: Factor::Tags,
+                contribution: 0.0,
+                detail: "no tags in common with completed katas".to_string(),
+            },
+        }
+    }
+
+    /// Katas at the user's rank fit best, each rank above fits less.
+    fn rank_fit(&self, rank: &Rank, user_level: i64) -> Reason {
+        let distance = level(rank.id) - user_level;
+        let detail = match distance {
+            0 => format!("{} as your rank", rank.name),
+            1 => format!("{}, one rank above yours", rank.name),
+            _ => format!("{}, {} ranks above yours", rank.name, distance),
+        };
+        Reason {
+            factor: Factor::Rank,
+            contribution: self.weights.rank * (1.0 - distance as f64 / (self.stretch + 1) as f64),
+            detail,
+        }
+    }
+
+    /// Returns recommendations for a stored user, with candidates drawn from katas whose
+    /// details are stored.
+    #[cfg(feature = "store")]
+    pub fn recommend_from_store(
+        &self,
+        store: &Store,
+        username: &str,
+        language: &str,
+    ) -> Result<Vec<Recommendation>, Error> {
+        let user = store.user(username)?.ok_or_else(|| Error::UserNotFound {
+            username: username.to_string(),
+        })?;
+        let completed = store.completed_challenges(username)?;
+        let challenges = store.code_challenges()?;
+        Ok(self.recommend(&user, &completed, &challenges, language))
+    }
+}
+
+impl Default for Recommender {
+    /// Returns a new instance of Recommender struct with default values.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Factor, Recommender};
+    use crate::code_challenges::CodeChallenge;
+    use crate::user::{OverallRank, User};
+    use crate::user_challenges::CompletedChallenge;
+
+    fn challenge(id: &str, rank: i64, stars: u64, tags: &[&str]) -> CodeChallenge {
+        let mut challenge = CodeChallenge::new();
+        challenge.id = id.to_string();
+        challenge.name = format!("Kata {}", id);
+        challenge.rank.id = rank;
+        challenge.rank.name = if rank < 0 {
+            format!("{} kyu", -rank)
+        } else {
+            format!("{} dan", rank)
+        };
+        challenge.languages = vec!["rust".to_string()];
+        challenge.total_stars = stars;
+        challenge.total_attempts = 100;
+        challenge.total_completed = 50;
+        challenge.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        challenge
+    }
+
+    #[test]
+    fn test_recommend() {
+        let mut user = User::new();
+        let mut rank = OverallRank::new();
+        rank.rank = -1;
+        user.ranks.languages.insert("rust".to_string(), rank);
+        let mut done = CompletedChallenge::new();
+        done.id = "done".to_string();
+        done.completed_languages = vec!["rust".to_string()];
+
+        let challenges = vec![
+            challenge("done", -1, 500, &["Algorithms"]),
+            challenge("same", -1, 10, &["Algorithms"]),
+            challenge("dan", 1, 10, &["Strings"]),
+            challenge("easy", -2, 900, &["Algorithms"]),
+            challenge("hard", 2, 900, &["Algorithms"]),
+        ];
+        let recommendations = Recommender::new().recommend(&user, &[done], &challenges, "rust");
+        let ids: Vec<&str> = recommendations
+            .iter()
+            .map(|r| r.challenge.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["same", "dan"]);
+
+        let same = &recommendations[0];
+        assert_eq!(same.reasons[0].factor, Factor::Tags);
+        assert!(same
+            .explain()
+            .starts_with("tagged Algorithms (1 completed)"));
+        assert!(same.explain().contains("1 kyu as your rank"));
+        assert!(recommendations[1]
+            .explain()
+            .contains("1 dan, one rank above yours"));
+
+        let stretched = Recommender::new().with_stretch(2).with_limit(1).recommend(
+            &user,
+            &[],
+            &challenges,
+            "rust",
+        );
+        assert_eq!(stretched.len(), 1);
+        assert_eq!(stretched[0].challenge.id, "done");
+    }
+
+    #[test]
+    fn test_recommend_skips_completed_and_unranked() {
+        let mut user = User::new();
+        let mut rank = OverallRank::new();
+        rank.rank = -8;
+        user.ranks.languages.insert("rust".to_string(), rank);
+        let mut in_python = CompletedChallenge::new();
+        in_python.id = "python".to_string();
+        in_python.completed_languages = vec!["python".to_string()];
+
+        let challenges = vec![
+            challenge("python", -8, 10, &[]),
+            challenge("beta", 0, 900, &[]),
+            challenge("new", -8, 10, &[]),
+        ];
+        let completed = [in_python];
+        let ids = |recommender: Recommender| -> Vec<String> {
+            recommender
+                .recommend(&user, &completed, &challenges, "rust")
+                .into_iter()
+                .map(|r| r.challenge.id)
+                .collect()
+        };
+        assert_eq!(ids(Recommender::new()), vec!["new"]);
+        assert_eq!(
+            ids(Recommender::new().with_other_languages(true)),
+            vec!["new", "python"]
+        );
+    }
+}
@@ -0,0 +1,669 @@
+//! Local workspaces for solving a code challenge.
+//!
+//! A workspace is generated from templates: a common template (README and metadata by
+//! default) and a template for the chosen language with a solution and test skeleton.
+//! Templates are plain text with `{{placeholder}}` markers and can be replaced in code
+//! or loaded from a directory.
+
+use crate::code_challenges::CodeChallenge;
+use crate::description::{self, Example};
+use crate::err::{Error, IoError, JsonError};
+use serde::Serialize;
+use snafu::ResultExt;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const README: &str = "# {{name}}
+
+{{rank}} · {{category}} · [{{url}}]({{url}})
+
+{{description}}
+";
+
+const RUST_CARGO: &str = "[package]
+name = \"{{identifier}}\"
+version = \"0.1.0\"
+edition = \"2018\"
+
+[dependencies]
+";
+
+const RUST_LIB: &str = "// {{name}}
+// {{url}}
+
+pub fn solution() {
+    todo!()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::solution;
+
+    #[test]
+    fn test_solution() {
+        {{tests}}
+    }
+}
+";
+
+const PYTHON_SOLUTION: &str = "# {{name}}
+# {{url}}
+
+
+def solution():
+    raise NotImplementedError
+";
+
+const PYTHON_TEST: &str = "import unittest
+
+from solution import solution
+
+
+class TestSolution(unittest.TestCase):
+    def test_solution(self):
+        {{tests}}
+
+
+if __name__ == \"__main__\":
+    unittest.main()
+";
+
+const JAVASCRIPT_SOLUTION: &str = "// {{name}}
+// {{url}}
+
+function solution() {
+  throw new Error(\"Not implemented\");
+}
+
+module.exports = { solution };
+";
+
+const JAVASCRIPT_TEST: &str = "const assert = require(\"assert\");
+const { solution } = require(\"./solution\");
+
+describe(\"{{name}}\", () => {
+  it(\"solves\", () => {
+    {{tests}}
+  });
+});
+";
+
+const GO_MOD: &str = "module {{identifier}}
+
+go 1.16
+";
+
+const GO_SOLUTION: &str = "// {{name}}
+// {{url}}
+
+package kata
+
+func Solution() {
+	panic(\"not implemented\")
+}
+";
+
+const GO_TEST: &str = "package kata
+
+import \"testing\"
+
+func TestSolution(t *testing.T) {
+	{{tests}}
+}
+";
+
+/// A structure that holds files of a template as relative path and content pairs.
+/// Both paths and contents may contain placeholders.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Template {
+    pub files: BTreeMap<String, String>,
+}
+
+impl Template {
+    /// Returns a new empty template.
+    pub fn new() -> Self {
+        Template {
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a file to the template, replacing a file with the same path.
+    pub fn with_file(mut self, path: &str, content: &str) -> Self {
+        self.files.insert(path.to_string(), content.to_string());
+        self
+    }
+
+    /// Loads a template from all files below a directory.
+    pub fn from_dir(dir: &Path) -> io::Result<Self> {
+        let mut template = Template::new();
+        template.load_dir(dir, dir)?;
+        Ok(template)
+    }
+
+    fn load_dir(&mut self, root: &Path, dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.load_dir(root, &path)?;
+            } else {
+                let relative = path
+                    .strip_prefix(root)
+                    .map_err(io::Error::other)?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                self.files.insert(relative, fs::read_to_string(&path)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A structure that holds metadata of a code challenge written to `kata.json`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct KataMetadata {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    pub url: String,
+    pub rank: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub languages: Vec<String>,
+    /// Language the workspace was generated for.
+    pub language: String,
+}
+
+/// Generates kata workspaces from templates.
+#[derive(Debug, Clone)]
+pub struct Scaffold {
+    common: Template,
+    templates: HashMap<String, Template>,
+}
+
+impl Scaffold {
+    /// Returns a new instance of Scaffold struct with built-in templates for Rust, Python,
+    /// JavaScript and Go.
+    pub fn new() -> Self {
+        let mut templates = HashMap::new();
+        templates.insert(
+            "rust".to_string(),
+            Template::new()
+                .with_file("Cargo.toml", RUST_CARGO)
+                .with_file("src/lib.rs", RUST_LIB),
+        );
+        templates.insert(
+            "python".to_string(),
+            Template::new()
+                .with_file("solution.py", PYTHON_SOLUTION)
+                .with_file("test_solution.py", PYTHON_TEST),
+        );
+        templates.insert(
+            "javascript".to_string(),
+            Template::new()
+                .with_file("solution.js", JAVASCRIPT_SOLUTION)
+                .with_file("solution.test.js", JAVASCRIPT_TEST),
+        );
+        templates.insert(
+            "go".to_string(),
+            Template::new()
+                .with_file("go.mod", GO_MOD)
+                .with_file("solution.go", GO_SOLUTION)
+                .with_file("solution_test.go", GO_TEST),
+        );
+        Scaffold {
+            common: Template::new()
+                .with_file("README.md", README)
+                .with_file("kata.json", "{{metadata}}\n"),
+            templates,
+        }
+    }
+
+    /// Replaces the template used for a language. Aliases like `js` name the same
+    /// language as `javascript`.
+    pub fn with_template(mut self, language: &str, template: Template) -> Self {
+        self.templates
+            .insert(description::normalize(language), template);
+        self
+    }
+
+    /// Replaces the template generated for every language.
+    pub fn with_common_template(mut self, template: Template) -> Self {
+        self.common = template;
+        self
+    }
+
+    /// Loads templates from a directory. Subdirectory `common` replaces the common
+    /// template and every other subdirectory replaces the template of the language it
+    /// is named after.
+    pub fn with_template_dir(mut self, dir: &Path) -> Result<Self, Error> {
+        for entry in fs::read_dir(dir).context(IoError)? {
+            let path = entry.context(IoError)?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let template = Template::from_dir(&path).context(IoError)?;
+            if name == "common" {
+                self.common = template;
+            } else {
+                self.templates
+                    .insert(description::normalize(&name), template);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns languages that have a template.
+    pub fn languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self.templates.keys().cloned().collect();
+        languages.sort();
+        languages
+    }
+
+    /// Generates a workspace for a challenge in given language below `dir` and returns
+    /// paths of created files. The language may be given in any case or by an alias
+    /// like `js`. Existing files are never overwritten, and if a file can't be written,
+    /// files and directories created so far are removed again.
+    pub fn generate(
+        &self,
+        challenge: &CodeChallenge,
+        language: &str,
+        dir: &Path,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let normalized = description::normalize(language);
+        if !challenge
+            .languages
+            .iter()
+            .any(|l| description::normalize(l) == normalized)
+        {
+            return Err(Error::LanguageNotSupported {
+                language: language.to_string(),
+                challenge_title: challenge.slug.clone(),
+            });
+        }
+        let template = match self.templates.get(&normalized) {
+            Some(template) => template,
+            None => {
+                return Err(Error::TemplateNotFound {
+                    language: language.to_string(),
+                })
+            }
+        };
+
+        let placeholders = placeholders(challenge, &normalized)?;
+        let files: Vec<(PathBuf, String)> = self
+            .common
+            .files
+            .iter()
+            .chain(template.files.iter())
+            .map(|(path, content)| {
+                (
+                    dir.join(render(path, &placeholders)),
+                    render(content, &placeholders),
+                )
+            })
+            .collect();
+        if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ))
+            .context(IoError);
+        }
+
+        let mut created = vec![];
+        let mut created_dirs = vec![];
+        if let Err(e) = write_files(&files, &mut created, &mut created_dirs) {
+            for path in created.iter().rev() {
+                let _ = fs::remove_file(path);
+            }
+            for dir in created_dirs.iter().rev() {
+                let _ = fs::remove_dir_all(dir);
+            }
+            return Err(e).context(IoError);
+        }
+        Ok(created)
+    }
+}
+
+/// Writes files that don't exist yet. Created files and the outermost directories
+/// created for them are recorded as they are created, so they can be removed on error.
+fn write_files(
+    files: &[(PathBuf, String)],
+    created: &mut Vec<PathBuf>,
+    created_dirs: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    for (path, content) in files {
+        if let Some(parent) = path.parent() {
+            let outermost_missing = parent.ancestors().take_while(|dir| !dir.exists()).last();
+            if let Some(outermost_missing) = outermost_missing {
+                let outermost_missing = outermost_missing.to_path_buf();
+                fs::create_dir_all(parent)?;
+                created_dirs.push(outermost_missing);
+            }
+        }
+        let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+        created.push(path.clone());
+        file.write_all(content.as_bytes())?;
+    }
+    Ok(())
+}
+
+impl Default for Scaffold {
+    /// Returns a new instance of Scaffold struct with default values.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the body of the test skeleton. The skeleton's `solution` takes no parameters,
+/// since their types can't be told from the description, so an assertion for every
+/// example found in the description is added commented out, to be enabled once the
+/// parameters are added.
+fn tests(language: &str, examples: &[Example]) -> String {
+    let function = if language == "go" {
+        "Solution"
+    } else {
+        "solution"
+    };
+    let (comment, call) = match language {
+        "rust" => ("//", "solution();"),
+        "python" => ("#", "solution()"),
+        "javascript" => ("//", "assert.doesNotThrow(() => solution());"),
+        "go" => ("//", "Solution()"),
+        _ => {
+            return examples
+                .iter()
+                .map(|e| format!("{} => {}", e.input, e.output))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    };
+    let mut lines = vec![];
+    if !examples.is_empty() {
+        lines.push(format!(
+            "{} TODO: add parameters to `{}` and uncomment the examples",
+            comment, function
+        ));
+    }
+    for example in examples {
+        let input = example_call(function, &example.input);
+        let assertion = match language {
+            "rust" => format!("assert_eq!({}, {});", input, example.output),
+            "python" => format!("self.assertEqual({}, {})", input, example.output),
+            "javascript" => format!("assert.deepStrictEqual({}, {});", input, example.output),
+            _ => format!(
+                "if got := {}; got != {} {{ t.Errorf(\"got %v\", got) }}",
+                input, example.output
+            ),
+        };
+        lines.push(format!("{} {}", comment, assertion));
+    }
+    lines.push(call.to_string());
+    lines.join("\n")
+}
+
+/// Returns a call of `function` for an example input. Examples often show the call
+/// itself with the kata's own function name, e.g. `valid_braces("()") => true`, which is
+/// renamed; other inputs become the arguments.
+fn example_call(function: &str, input: &str) -> String {
+    if let Some(open) = input.find('(') {
+        let name = &input[..open];
+        let is_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+        // The parenthesis opened after the name must be the one closing the input
+        let mut depth = 0;
+        let closes_at_end = input[open..].char_indices().all(|(index, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            depth > 0 || open + index == input.len() - 1
+        });
+        if is_name && closes_at_end && depth == 0 {
+            return format!("{}{}", function, &input[open..]);
+        }
+    }
+    format!("{}({})", function, input)
+}
+
+/// Returns an identifier usable as a package or module name, e.g. `valid_braces`.
+fn identifier(slug: &str) -> String {
+    let identifier: String = slug
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if identifier.starts_with(|c: char| !c.is_ascii_alphabetic()) {
+        format!("kata_{}", identifier)
+    } else {
+        identifier
+    }
+}
+
+fn placeholders(challenge: &CodeChallenge, language: &str) -> Result<Vec<(String, String)>, Error> {
+    let metadata = KataMetadata {
+        id: challenge.id.clone(),
+        name: challenge.name.clone(),
+        slug: challenge.slug.clone(),
+        url: challenge.url.clone(),
+        rank: challenge.rank.name.clone(),
+        category: challenge.category.clone(),
+        tags: challenge.tags.clone(),
+        languages: challenge.languages.clone(),
+        language: language.to_string(),
+    };
+    let values = vec![
+        ("id", challenge.id.clone()),
+        ("name", challenge.name.clone()),
+        ("slug", challenge.slug.clone()),
+        ("identifier", identifier(&challenge.slug)),
+        ("url", challenge.url.clone()),
+        ("rank", challenge.rank.name.clone()),
+        ("category", challenge.category.clone()),
+        ("tags", challenge.tags.join(", ")),
+        ("languages", challenge.languages.join(", ")),
+        ("language", language.to_string()),
+        (
+            "description",
+            description::filter(&challenge.description, language),
+        ),
+        ("tests", tests(language, &challenge.examples(language))),
+        (
+            "metadata",
+            serde_json::to_string_pretty(&metadata).context(JsonError)?,
+        ),
+    ];
+    Ok(values
+        .into_iter()
+        .map(|(key, value)| (format!("{{{{{}}}}}", key), value))
+        .collect())
+}
+
+fn render(template: &str, placeholders: &[(String, String)]) -> String {
+    // Replace in a single pass so placeholders inside values are kept as they are
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholders
+            .iter()
+            .find(|(key, _)| rest.starts_with(key.as_str()))
+        {
+            Some((key, value)) => {
+                // Lines of a multi-line value are indented like the placeholder
+                let line_start = rendered.rfind('\n').map_or(0, |at| at + 1);
+                let indent = &rendered[line_start..];
+                if indent.chars().all(char::is_whitespace) && !indent.is_empty() {
+                    let value = value.replace('\n', &format!("\n{}", indent));
+                    rendered.push_str(&value);
+                } else {
+                    rendered.push_str(value);
+                }
+                rest = &rest[key.len()..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = &rest[2..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{example_call, identifier, Scaffold, Template};
+    use crate::code_challenges::CodeChallenge;
+    use crate::err::Error;
+    use std::fs;
+    use std::process::Command;
+
+    fn challenge() -> CodeChallenge {
+        let mut challenge = CodeChallenge::new();
+        challenge.id = "5277c8a221e209d3f6000b56".to_string();
+        challenge.name = "Valid Braces".to_string();
+        challenge.slug = "valid-braces".to_string();
+        challenge.url = "https://www.codewars.com/kata/valid-braces".to_string();
+        challenge.rank.name = "6 kyu".to_string();
+        challenge.description = "Write a function that takes a string of braces {{x}}.\n\
+            ```if:python\n\
+            Return a bool.\n\
+            ```\n\
+            ```rust\n\
+            valid_braces(\"(){}[]\") // => true\n\
+            \"([)]\" => false\n\
+            ```"
+        .to_string();
+        challenge.languages = vec!["rust".to_string(), "python".to_string()];
+        challenge.tags = vec!["Algorithms".to_string()];
+        challenge
+    }
+
+    #[test]
+    fn test_generate() {
+        let dir = tempfile::tempdir().unwrap();
+        let created = Scaffold::new()
+            .generate(&challenge(), "rust", dir.path())
+            .unwrap();
+        assert_eq!(created.len(), 4);
+
+        let readme = fs::read_to_string(dir.path().join("README.md")).unwrap();
+        assert!(readme.starts_with("# Valid Braces\n"));
+        assert!(readme.contains("string of braces {{x}}."));
+        assert!(!readme.contains("Return a bool."));
+        let metadata: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("kata.json")).unwrap())
+                .unwrap();
+        assert_eq!(metadata["rank"], "6 kyu");
+        assert_eq!(metadata["language"], "rust");
+        let cargo = fs::read_to_string(dir.path().join("Cargo.toml")).unwrap();
+        assert!(cargo.contains("name = \"valid_braces\""));
+        let lib = fs::read_to_string(dir.path().join("src/lib.rs")).unwrap();
+        assert!(lib.contains(
+            "        // TODO: add parameters to `solution` and uncomment the examples\n        \
+            // assert_eq!(solution(\"(){}[]\"), true);\n        \
+            // assert_eq!(solution(\"([)]\"), false);\n        \
+            solution();\n"
+        ));
+
+        // Existing files are not overwritten
+        assert!(matches!(
+            Scaffold::new().generate(&challenge(), "rust", dir.path()),
+            Err(Error::IoError { .. })
+        ));
+
+        // Languages match in any case and by alias
+        let created = Scaffold::new()
+            .generate(&challenge(), "Rust", &dir.path().join("upper"))
+            .unwrap();
+        assert_eq!(created.len(), 4);
+        let created = Scaffold::new()
+            .generate(&challenge(), "py", &dir.path().join("alias"))
+            .unwrap();
+        assert!(created.contains(&dir.path().join("alias/solution.py")));
+    }
+
+    #[test]
+    fn test_generated_rust_workspace_compiles() {
+        let dir = tempfile::tempdir().unwrap();
+        Scaffold::new()
+            .generate(&challenge(), "rust", &dir.path().join("kata"))
+            .unwrap();
+        let status = Command::new(env!("CARGO"))
+            .args(["check", "--all-targets", "--offline", "--quiet"])
+            .current_dir(dir.path().join("kata"))
+            .env("CARGO_TARGET_DIR", dir.path().join("target"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_example_call() {
+        assert_eq!(
+            example_call("solution", "valid_braces(\"()\")"),
+            "solution(\"()\")"
+        );
+        assert_eq!(
+            example_call("Solution", "Kata.f(1, g(2))"),
+            "Solution(1, g(2))"
+        );
+        assert_eq!(
+            example_call("solution", "f(1) + f(2)"),
+            "solution(f(1) + f(2))"
+        );
+        assert_eq!(example_call("solution", "[1, 2]"), "solution([1, 2])");
+    }
+
+    #[test]
+    fn test_generate_removes_partial_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        // `solution` is written as a file before `solution/lib.rs` needs it as a directory
+        let scaffold = Scaffold::new().with_template(
+            "rust",
+            Template::new()
+                .with_file("solution", "")
+                .with_file("solution/lib.rs", ""),
+        );
+        assert!(matches!(
+            scaffold.generate(&challenge(), "rust", &out),
+            Err(Error::IoError { .. })
+        ));
+        assert!(!out.exists());
+
+        fs::create_dir(&out).unwrap();
+        fs::write(out.join("notes.txt"), "mine").unwrap();
+        assert!(scaffold.generate(&challenge(), "rust", &out).is_err());
+        let left: Vec<_> = fs::read_dir(&out).unwrap().collect();
+        assert_eq!(left.len(), 1);
+    }
+
+    #[test]
+    fn test_generate_refuses_languages() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            Scaffold::new().generate(&challenge(), "go", dir.path()),
+            Err(Error::LanguageNotSupported { .. })
+        ));
+
+        let scaffold = Scaffold::new().with_template(
+            "python",
+            Template::new().with_file("{{identifier}}.py", "pass\n"),
+        );
+        let created = scaffold
+            .generate(&challenge(), "python", dir.path())
+            .unwrap();
+        assert!(created.contains(&dir.path().join("valid_braces.py")));
+        assert_eq!(identifier("2-sum"), "kata_2_sum");
+    }
+}
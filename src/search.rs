@@ -0,0 +1,426 @@
+//! Full-text search over code challenges kept locally.
+//!
+//! [`Index`] tokenizes name, description, tags and category of every challenge and
+//! ranks matches with BM25, weighting a match in the name above one in the description.
+//! Queries mix free words, quoted phrases and filters:
+//!
+//! ```text
+//! tag:arrays rank:<=5kyu lang:rust "dynamic programming"
+//! ```
+//!
+//! Filters are `tag:`, `category:`, `lang:` and `rank:` with an optional `<`, `<=`, `>`
+//! or `>=` before a rank like `5kyu` or `1dan`, where harder ranks are greater. Values
+//! with spaces are quoted, e.g. `tag:"dynamic programming"`. Queries with filters only
+//! list matching challenges by quality.
+
+use crate::code_challenges::CodeChallenge;
+use crate::description;
+use crate::err::Error;
+#[cfg(feature = "store")]
+use crate::store::Store;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Field weights of name, tags, category and description.
+const WEIGHTS: [f64; 4] = [3.0, 2.0, 1.5, 1.0];
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// How a rank filter compares ranks.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankFilter {
+    Below(i64),
+    AtMost(i64),
+    Exactly(i64),
+    AtLeast(i64),
+    Above(i64),
+}
+
+impl RankFilter {
+    /// Returns whether a rank id passes the filter.
+    pub fn matches(&self, rank: i64) -> bool {
+        match *self {
+            RankFilter::Below(id) => rank < id,
+            RankFilter::AtMost(id) => rank <= id,
+            RankFilter::Exactly(id) => rank == id,
+            RankFilter::AtLeast(id) => rank >= id,
+            RankFilter::Above(id) => rank > id,
+        }
+    }
+}
+
+/// A structure that holds a parsed search query.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    /// Lowercase words that rank results. A challenge matches if it has any of them.
+    pub terms: Vec<String>,
+    /// Phrases as lowercase words. A challenge must have every phrase in one field.
+    pub phrases: Vec<Vec<String>>,
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+    /// Canonical language names.
+    pub languages: Vec<String>,
+    pub ranks: Vec<RankFilter>,
+}
+
+/// Returns lowercase alphanumeric words of a text.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Returns rank id of `5kyu` or `1dan`.
+fn parse_rank(value: &str) -> Option<i64> {
+    let value = value.to_lowercase();
+    let (number, sign) = if let Some(number) = value.strip_suffix("kyu") {
+        (number, -1)
+    } else if let Some(number) = value.strip_suffix("dan") {
+        (number, 1)
+    } else {
+        return None;
+    };
+    match number.trim().parse::<i64>() {
+        Ok(number) if (1..=8).contains(&number) => Some(sign * number),
+        _ => None,
+    }
+}
+
+impl Query {
+    /// Parses a query.
+    pub fn parse(query: &str) -> Result<Self, Error> {
+        let invalid = |reason: String| Error::InvalidQuery {
+            query: query.to_string(),
+            reason,
+        };
+
+        // Split on whitespace outside quotes, keeping whether a part was quoted
+        let mut parts: Vec<(String, bool)> = vec![];
+        let mut current = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        for c in query.chars() {
+            match c {
+                '"' => {
+                    in_quotes = !in_quotes;
+                    quoted = true;
+                }
+                c if c.is_whitespace() && !in_quotes => {
+                    if !current.is_empty() || quoted {
+                        parts.push((current.clone(), quoted));
+                    }
+                    current.clear();
+                    quoted = false;
+                }
+                c => current.push(c),
+            }
+        }
+        if in_quotes {
+            return Err(invalid("unclosed quote".to_string()));
+        }
+        if !current.is_empty() || quoted {
+            parts.push((current, quoted));
+        }
+
+        let mut parsed = Query::default();
+        for (part, quoted) in parts {
+            let filter = part
+                .split_once(':')
+                .filter(|(key, _)| !key.is_empty() && key.chars().all(char::is_alphabetic));
+            match filter {
+                Some((key, value)) => {
+                    if value.trim().is_empty() {
+                        return Err(invalid(format!("{} has no value", key)));
+                    }
+                    match key.to_lowercase().as_str() {
+                        "tag" => parsed.tags.push(value.to_lowercase()),
+                        "category" => parsed.categories.push(value.to_lowercase()),
+                        "lang" | "language" => parsed.languages.push(description::normalize(value)),
+                        "rank" => parsed
+                            .ranks
+                            .push(Query::parse_rank_filter(value).ok_or_else(|| {
+                                invalid(format!("{} is not a rank like <=5kyu", value))
+                            })?),
+                        other => return Err(invalid(format!("unknown filter {}", other))),
+                    }
+                }
+                None if quoted => {
+                    let phrase = tokenize(&part);
+                    if !phrase.is_empty() {
+                        parsed.phrases.push(phrase);
+                    }
+                }
+                None => parsed.terms.extend(tokenize(&part)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn parse_rank_filter(value: &str) -> Option<RankFilter> {
+        if let Some(rank) = value.strip_prefix("<=") {
+            parse_rank(rank).map(RankFilter::AtMost)
+        } else if let Some(rank) = value.strip_prefix(">=") {
+            parse_rank(rank).map(RankFilter::AtLeast)
+        } else if let Some(rank) = value.strip_prefix('<') {
+            parse_rank(rank).map(RankFilter::Below)
+        } else if let Some(rank) = value.strip_prefix('>') {
+            parse_rank(rank).map(RankFilter::Above)
+        } else {
+            parse_rank(value.trim_start_matches('=')).map(RankFilter::Exactly)
+        }
+    }
+
+    /// Returns whether a challenge passes all filters and contains all phrases.
+    fn matches(&self, challenge: &CodeChallenge, fields: &[Vec<String>; 4]) -> bool {
+        let lowercase = |values: &[String]| -> Vec<String> {
+            values.iter().map(|value| value.to_lowercase()).collect()
+        };
+        let tags = lowercase(&challenge.tags);
+        let languages: Vec<String> = challenge
+            .languages
+            .iter()
+            .map(|language| description::normalize(language))
+            .collect();
+        self.tags.iter().all(|tag| tags.contains(tag))
+            && self
+                .categories
+                .iter()
+                .all(|category| challenge.category.to_lowercase() == *category)
+            && self
+                .languages
+                .iter()
+                .all(|language| languages.contains(language))
+            && self
+                .ranks
+                .iter()
+                .all(|filter| filter.matches(challenge.rank.id))
+            && self.phrases.iter().all(|phrase| {
+                fields.iter().any(|field| {
+                    field
+                        .windows(phrase.len())
+                        .any(|window| window == phrase.as_slice())
+                })
+            })
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+/// A structure that holds a challenge matching a query.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Hit<'a> {
+    pub challenge: &'a CodeChallenge,
+    /// Relevance to free words and phrases. Zero for queries with filters only.
+    pub score: f64,
+}
+
+/// A structure that holds an in-memory search index of code challenges.
+#[derive(Debug, Clone, Default)]
+pub struct Index {
+    challenges: Vec<CodeChallenge>,
+    /// Tokens of name, tags, category and description of every challenge.
+    fields: Vec<[Vec<String>; 4]>,
+    /// Weighted term frequencies of every challenge.
+    frequencies: Vec<HashMap<String, f64>>,
+    /// Weighted lengths of every challenge.
+    lengths: Vec<f64>,
+    /// Number of challenges containing a term.
+    document_frequencies: HashMap<String, usize>,
+}
+
+impl Index {
+    /// Returns an index of code challenges.
+    pub fn new(challenges: Vec<CodeChallenge>) -> Self {
+        let mut index = Index::default();
+        for challenge in challenges {
+            index.add(challenge);
+        }
+        index
+    }
+
+    /// Returns an index of all code challenges whose details are stored.
+    #[cfg(feature = "store")]
+    pub fn from_store(store: &Store) -> Result<Self, Error> {
+        Ok(Index::new(store.code_challenges()?))
+    }
+
+    /// Adds a code challenge to the index.
+    pub fn add(&mut self, challenge: CodeChallenge) {
+        let fields = [
+            tokenize(&challenge.name),
+            tokenize(&challenge.tags.join(" ")),
+            tokenize(&challenge.category),
+            tokenize(&challenge.description),
+        ];
+        let mut frequencies: HashMap<String, f64> = HashMap::new();
+        let mut length = 0.0;
+        for (field, weight) in fields.iter().zip(WEIGHTS.iter()) {
+            for token in field {
+                *frequencies.entry(token.clone()).or_insert(0.0) += weight;
+                length += weight;
+            }
+        }
+        for term in frequencies.keys() {
+            *self.document_frequencies.entry(term.clone()).or_insert(0) += 1;
+        }
+        self.challenges.push(challenge);
+        self.fields.push(fields);
+        self.frequencies.push(frequencies);
+        self.lengths.push(length);
+    }
+
+    /// Returns the number of indexed challenges.
+    pub fn len(&self) -> usize {
+        self.challenges.len()
+    }
+
+    /// Returns whether the index has no challenges.
+    pub fn is_empty(&self) -> bool {
+        self.challenges.is_empty()
+    }
+
+    /// Returns up to `limit` challenges matching a query, most relevant first. Equally
+    /// relevant challenges are ordered by quality score.
+    pub fn search(&self, query: &Query, limit: usize) -> Vec<Hit<'_>> {
+        let mut terms: Vec<&String> = query
+            .terms
+            .iter()
+            .chain(query.phrases.iter().flatten())
+            .collect();
+        terms.sort();
+        terms.dedup();
+        let average_length = self.lengths.iter().sum::<f64>() / self.len().max(1) as f64;
+
+        let mut hits: Vec<Hit<'_>> = vec![];
+        for (n, challenge) in self.challenges.iter().enumerate() {
+            if !query.matches(challenge, &self.fields[n]) {
+                continue;
+            }
+            let mut score = 0.0;
+            for term in &terms {
+                let frequency = match self.frequencies[n].get(*term) {
+                    Some(frequency) => *frequency,
+                    None => continue,
+                };
+                let documents = self.document_frequencies[*term] as f64;
+                let idf = (1.0 + (self.len() as f64 - documents + 0.5) / (documents + 0.5)).ln();
+                let norm = K1 * (1.0 - B + B * self.lengths[n] / average_length.max(1.0));
+                score += idf * frequency * (K1 + 1.0) / (frequency + norm);
+            }
+            if !query.terms.is_empty() && score == 0.0 {
+                continue;
+            }
+            hits.push(Hit { challenge, score });
+        }
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(
+                    b.challenge
+                        .quality_score()
+                        .total_cmp(&a.challenge.quality_score()),
+                )
+                .then(a.challenge.name.cmp(&b.challenge.name))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Index, Query, RankFilter};
+    use crate::code_challenges::CodeChallenge;
+    use crate::err::Error;
+
+    fn challenge(name: &str, rank: i64, tags: &[&str], description: &str) -> CodeChallenge {
+        let mut challenge = CodeChallenge::new();
+        challenge.id = name.to_lowercase().replace(' ', "-");
+        challenge.name = name.to_string();
+        challenge.rank.id = rank;
+        challenge.category = "algorithms".to_string();
+        challenge.languages = vec!["rust".to_string(), "javascript".to_string()];
+        challenge.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        challenge.description = description.to_string();
+        challenge
+    }
+
+    #[test]
+    fn test_query_parse() {
+        let query =
+            Query::parse("tag:arrays rank:<=5kyu lang:js \"Dynamic  programming\" Sum").unwrap();
+        assert_eq!(query.tags, vec!["arrays"]);
+        assert_eq!(query.ranks, vec![RankFilter::AtMost(-5)]);
+        assert_eq!(query.languages, vec!["javascript"]);
+        assert_eq!(query.phrases, vec![vec!["dynamic", "programming"]]);
+        assert_eq!(query.terms, vec!["sum"]);
+        assert_eq!(
+            Query::parse("tag:\"dynamic programming\" rank:2dan").unwrap(),
+            Query {
+                tags: vec!["dynamic programming".to_string()],
+                ranks: vec![RankFilter::Exactly(2)],
+                ..Query::default()
+            }
+        );
+        assert!(matches!(
+            Query::parse("rank:<=9kyu"),
+            Err(Error::InvalidQuery { .. })
+        ));
+        assert!(Query::parse("author:me").is_err());
+        assert!(Query::parse("\"open").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let index = Index::new(vec![
+            challenge(
+                "Max Subarray Sum",
+                -5,
+                &["Arrays", "Dynamic Programming"],
+                "Use dynamic programming to find the maximum sum of a subarray.",
+            ),
+            challenge(
+                "Sum of Pairs",
+                -5,
+                &["Arrays"],
+                "Find the first pair with the given sum. Programming is fun, dynamic too.",
+            ),
+            challenge(
+                "Knapsack",
+                -3,
+                &["Dynamic Programming"],
+                "A classic dynamic programming problem.",
+            ),
+        ]);
+        assert_eq!(index.len(), 3);
+
+        let names = |query: &str| -> Vec<String> {
+            index
+                .search(&query.parse().unwrap(), 10)
+                .iter()
+                .map(|hit| hit.challenge.name.clone())
+                .collect()
+        };
+        assert_eq!(
+            names("tag:arrays rank:<=5kyu lang:rust \"dynamic programming\""),
+            vec!["Max Subarray Sum"]
+        );
+        assert_eq!(
+            names("maximum sum"),
+            vec!["Max Subarray Sum", "Sum of Pairs"]
+        );
+        assert_eq!(names("rank:>5kyu"), vec!["Knapsack"]);
+        assert_eq!(names("tag:\"dynamic programming\"").len(), 2);
+        assert!(names("lang:python").is_empty());
+        assert!(names("nothing").is_empty());
+    }
+}
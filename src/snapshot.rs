@@ -0,0 +1,222 @@
+//! Timestamped snapshots of users and differences between them.
+
+use crate::user::{OverallRank, User};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A structure that holds a user as it was at a point in time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UserSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub user: User,
+}
+
+impl UserSnapshot {
+    /// Returns a new snapshot of a user taken now.
+    pub fn new(user: User) -> Self {
+        UserSnapshot {
+            taken_at: Utc::now(),
+            user,
+        }
+    }
+
+    /// Returns a new snapshot of a user taken at given time.
+    pub fn taken_at(user: User, taken_at: DateTime<Utc>) -> Self {
+        UserSnapshot { taken_at, user }
+    }
+}
+
+/// A structure that holds a change of the overall rank or rank in a language.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RankChange {
+    /// Language of the rank, or `None` for the overall rank.
+    pub language: Option<String>,
+    /// Rank before the change, or `None` if the user had no rank in the language.
+    pub from: Option<OverallRank>,
+    pub to: OverallRank,
+}
+
+/// A structure that holds a change of the clan a user belongs to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClanChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// A structure that holds differences between two snapshots of the same user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UserDiff {
+    pub username: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub honor_delta: i64,
+    pub score_delta: i64,
+    pub overall_rank_up: Option<RankChange>,
+    /// Languages in which rank went up, including languages the user started using.
+    pub rank_ups: Vec<RankChange>,
+    pub new_skills: Vec<String>,
+    pub clan_change: Option<ClanChange>,
+    /// Positions gained on the leaderboard. Negative when the user dropped. `None` if
+    /// either snapshot has no leaderboard position.
+    pub leaderboard_movement: Option<i64>,
+    pub completed_delta: i64,
+    pub authored_delta: i64,
+}
+
+impl UserDiff {
+    /// Returns differences from `older` to `newer` snapshot.
+    pub fn between(older: &UserSnapshot, newer: &UserSnapshot) -> Self {
+        let old = &older.user;
+        let new = &newer.user;
+
+        let overall_rank_up = if new.ranks.overall.rank > old.ranks.overall.rank {
+            Some(RankChange {
+                language: None,
+                from: Some(old.ranks.overall.clone()),
+                to: new.ranks.overall.clone(),
+            })
+        } else {
+            None
+        };
+
+        let mut rank_ups: Vec<RankChange> = new
+            .ranks
+            .languages
+            .iter()
+            .filter_map(|(language, to)| {
+                let from = old.ranks.languages.get(language);
+                match from {
+                    Some(from) if from.rank >= to.rank => None,
+                    _ => Some(RankChange {
+                        language: Some(language.clone()),
+                        from: from.cloned(),
+                        to: to.clone(),
+                    }),
+                }
+            })
+            .collect();
+        rank_ups.sort_by(|a, b| a.language.cmp(&b.language));
+
+        let new_skills = new
+            .skills
+            .iter()
+            .filter(|skill| !old.skills.contains(skill))
+            .cloned()
+            .collect();
+
+        let clan_change = if old.clan != new.clan {
+            Some(ClanChange {
+                from: old.clan.clone(),
+                to: new.clan.clone(),
+            })
+        } else {
+            None
+        };
+
+        let leaderboard_movement = match (old.leaderboard_position, new.leaderboard_position) {
+            (Some(old_position), Some(new_position)) => Some(old_position - new_position),
+            _ => None,
+        };
+
+        UserDiff {
+            username: new.username.clone(),
+            from: older.taken_at,
+            to: newer.taken_at,
+            honor_delta: new.honor.unwrap_or(0) - old.honor.unwrap_or(0),
+            score_delta: new.ranks.overall.score as i64 - old.ranks.overall.score as i64,
+            overall_rank_up,
+            rank_ups,
+            new_skills,
+            clan_change,
+            leaderboard_movement,
+            completed_delta: new.code_challenges.total_completed as i64
+                - old.code_challenges.total_completed as i64,
+            authored_delta: new.code_challenges.total_authored as i64
+                - old.code_challenges.total_authored as i64,
+        }
+    }
+
+    /// Returns true if nothing changed between the snapshots.
+    pub fn is_empty(&self) -> bool {
+        self.honor_delta == 0
+            && self.score_delta == 0
+            && self.overall_rank_up.is_none()
+            && self.rank_ups.is_empty()
+            && self.new_skills.is_empty()
+            && self.clan_change.is_none()
+            && self.leaderboard_movement.unwrap_or(0) == 0
+            && self.completed_delta == 0
+            && self.authored_delta == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{UserDiff, UserSnapshot};
+    use crate::user::{OverallRank, User};
+    use chrono::{TimeZone, Utc};
+
+    fn rank(rank: i64, score: u64) -> OverallRank {
+        let mut overall_rank = OverallRank::new();
+        overall_rank.rank = rank;
+        overall_rank.name = format!("{} kyu", -rank);
+        overall_rank.score = score;
+        overall_rank
+    }
+
+    fn user() -> User {
+        let mut user = User::new();
+        user.username = "some_user".to_string();
+        user.honor = Some(100);
+        user.clan = Some("old clan".to_string());
+        user.leaderboard_position = Some(5000);
+        user.skills = vec!["rust".to_string()];
+        user.ranks.overall = rank(-6, 100);
+        user.ranks
+            .languages
+            .insert("rust".to_string(), rank(-6, 100));
+        user.ranks
+            .languages
+            .insert("python".to_string(), rank(-7, 50));
+        user
+    }
+
+    #[test]
+    fn test_diff() {
+        let older =
+            UserSnapshot::taken_at(user(), Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap());
+        let mut newer_user = user();
+        newer_user.honor = Some(130);
+        newer_user.clan = Some("new clan".to_string());
+        newer_user.leaderboard_position = Some(4800);
+        newer_user.skills.push("sql".to_string());
+        newer_user.ranks.overall = rank(-5, 180);
+        newer_user
+            .ranks
+            .languages
+            .insert("rust".to_string(), rank(-5, 180));
+        newer_user
+            .ranks
+            .languages
+            .insert("go".to_string(), rank(-8, 2));
+        let newer = UserSnapshot::taken_at(
+            newer_user,
+            Utc.with_ymd_and_hms(2020, 1, 8, 0, 0, 0).unwrap(),
+        );
+
+        let diff = UserDiff::between(&older, &newer);
+        assert_eq!(diff.honor_delta, 30);
+        assert_eq!(diff.score_delta, 80);
+        assert!(diff.overall_rank_up.is_some());
+        assert_eq!(diff.rank_ups.len(), 2);
+        assert_eq!(diff.rank_ups[0].language, Some("go".to_string()));
+        assert!(diff.rank_ups[0].from.is_none());
+        assert_eq!(diff.rank_ups[1].from.as_ref().unwrap().rank, -6);
+        assert_eq!(diff.new_skills, vec!["sql".to_string()]);
+        assert_eq!(diff.clan_change.unwrap().to, Some("new clan".to_string()));
+        assert_eq!(diff.leaderboard_movement, Some(200));
+
+        assert!(UserDiff::between(&older, &older).is_empty());
+    }
+}
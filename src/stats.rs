@@ -0,0 +1,247 @@
+//! Statistics over a user's completed challenges.
+//!
+//! All times are in UTC. Completions with a `completed_at` that can't be parsed are
+//! counted in totals and per language but not in time based statistics.
+
+use crate::err::{Error, JsonError};
+use crate::table;
+use crate::user_challenges::CompletedChallenge;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::{BTreeMap, BTreeSet};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// A structure that holds a run of consecutive days with at least one completion.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Streak {
+    pub days: u32,
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+/// A structure that holds statistics of completed challenges.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct UserStats {
+    pub total: usize,
+    /// Completions per language. A challenge completed in two languages counts for both.
+    pub per_language: BTreeMap<String, usize>,
+    /// Completions per month, keyed by `YYYY-MM`.
+    pub per_month: BTreeMap<String, usize>,
+    /// Completions per weekday, Monday first.
+    pub per_weekday: [usize; 7],
+    /// Completions per hour of day.
+    pub per_hour: [usize; 24],
+    /// Time of the first completion in every language.
+    pub first_seen: BTreeMap<String, DateTime<Utc>>,
+    /// Number of challenges completed in more than one language.
+    pub multi_language: usize,
+    /// Number of challenges by the number of languages they were completed in.
+    pub languages_per_challenge: BTreeMap<usize, usize>,
+    pub longest_streak: Streak,
+    /// Streak that ends today or yesterday, so it can still be continued.
+    pub current_streak: Streak,
+}
+
+impl UserStats {
+    /// Returns statistics of completed challenges as of today.
+    pub fn new(completed: &[CompletedChallenge]) -> Self {
+        Self::at(completed, Utc::now().date_naive())
+    }
+
+    /// Returns statistics of completed challenges with the current streak computed as of
+    /// given day.
+    pub fn at(completed: &[CompletedChallenge], today: NaiveDate) -> Self {
+        let mut stats = UserStats {
+            total: completed.len(),
+            ..UserStats::default()
+        };
+        let mut days = BTreeSet::new();
+        for challenge in completed {
+            let completed_at = challenge.completed_at_time();
+            for language in &challenge.completed_languages {
+                *stats.per_language.entry(language.clone()).or_insert(0) += 1;
+                if let Some(completed_at) = completed_at {
+                    let first_seen = stats
+                        .first_seen
+                        .entry(language.clone())
+                        .or_insert(completed_at);
+                    if completed_at < *first_seen {
+                        *first_seen = completed_at;
+                    }
+                }
+            }
+            let languages = challenge.completed_languages.len();
+            *stats.languages_per_challenge.entry(languages).or_insert(0) += 1;
+            if languages > 1 {
+                stats.multi_language += 1;
+            }
+
+            if let Some(completed_at) = completed_at {
+                *stats
+                    .per_month
+                    .entry(completed_at.format("%Y-%m").to_string())
+                    .or_insert(0) += 1;
+                stats.per_weekday[completed_at.weekday().num_days_from_monday() as usize] += 1;
+                stats.per_hour[completed_at.hour() as usize] += 1;
+                days.insert(completed_at.date_naive());
+            }
+        }
+
+        let mut current: Option<Streak> = None;
+        for day in days {
+            current = match current {
+                Some(mut streak) if streak.end.map(|end| end + Duration::days(1)) == Some(day) => {
+                    streak.days += 1;
+                    streak.end = Some(day);
+                    Some(streak)
+                }
+                _ => Some(Streak {
+                    days: 1,
+                    start: Some(day),
+                    end: Some(day),
+                }),
+            };
+            if let Some(streak) = &current {
+                if streak.days > stats.longest_streak.days {
+                    stats.longest_streak = streak.clone();
+                }
+            }
+        }
+        if let Some(streak) = current {
+            if streak
+                .end
+                .is_some_and(|end| end >= today - Duration::days(1))
+            {
+                stats.current_streak = streak;
+            }
+        }
+        stats
+    }
+
+    /// Returns statistics as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).context(JsonError)
+    }
+
+    /// Returns statistics as human-readable text with a table per statistic.
+    pub fn to_table(&self) -> String {
+        let streak = |streak: &Streak| match (streak.start, streak.end) {
+            (Some(start), Some(_)) if streak.days == 1 => format!("1 day ({})", start),
+            (Some(start), Some(end)) => format!("{} days ({} to {})", streak.days, start, end),
+            _ => "0 days".to_string(),
+        };
+        let mut sections = vec![format!(
+            "Completed: {} ({} in more than one language)\n\
+             Longest streak: {}\n\
+             Current streak: {}",
+            self.total,
+            self.multi_language,
+            streak(&self.longest_streak),
+            streak(&self.current_streak)
+        )];
+
+        let mut languages: Vec<(&String, &usize)> = self.per_language.iter().collect();
+        languages.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        sections.push(table::render(
+            &["Language", "Completions", "First seen"],
+            &languages
+                .into_iter()
+                .map(|(language, count)| {
+                    vec![
+                        language.clone(),
+                        count.to_string(),
+                        self.first_seen
+                            .get(language)
+                            .map(|time| time.date_naive().to_string())
+                            .unwrap_or_default(),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        ));
+        sections.push(table::render(
+            &["Month", "Completions"],
+            &self
+                .per_month
+                .iter()
+                .map(|(month, count)| vec![month.clone(), count.to_string()])
+                .collect::<Vec<_>>(),
+        ));
+        sections.push(table::render(
+            &["Weekday", "Completions"],
+            &WEEKDAYS
+                .iter()
+                .zip(self.per_weekday.iter())
+                .map(|(day, count)| vec![day.to_string(), count.to_string()])
+                .collect::<Vec<_>>(),
+        ));
+        sections.push(table::render(
+            &["Hour", "Completions"],
+            &self
+                .per_hour
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0)
+                .map(|(hour, count)| vec![format!("{:02}:00", hour), count.to_string()])
+                .collect::<Vec<_>>(),
+        ));
+        sections.join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::UserStats;
+    use crate::user_challenges::CompletedChallenge;
+    use chrono::NaiveDate;
+
+    fn completed(completed_at: &str, languages: &[&str]) -> CompletedChallenge {
+        let mut challenge = CompletedChallenge::new();
+        challenge.completed_at = completed_at.to_string();
+        challenge.completed_languages = languages.iter().map(|l| l.to_string()).collect();
+        challenge
+    }
+
+    #[test]
+    fn test_stats() {
+        let challenges = vec![
+            completed("2020-01-06T10:00:00Z", &["python"]),
+            completed("2020-01-07T10:30:00Z", &["python", "rust"]),
+            completed("2020-01-08T23:00:00Z", &["rust"]),
+            completed("2020-02-01T10:00:00Z", &["rust"]),
+            completed("2020-02-02T08:00:00Z", &["go"]),
+            completed("not a date", &["go"]),
+        ];
+        let stats = UserStats::at(&challenges, NaiveDate::from_ymd_opt(2020, 2, 3).unwrap());
+
+        assert_eq!(stats.total, 6);
+        assert_eq!(stats.per_language["rust"], 3);
+        assert_eq!(stats.per_language["go"], 2);
+        assert_eq!(stats.per_month["2020-01"], 3);
+        assert_eq!(stats.per_month["2020-02"], 2);
+        assert_eq!(stats.per_weekday, [1, 1, 1, 0, 0, 1, 1]);
+        assert_eq!(stats.per_hour[10], 3);
+        assert_eq!(
+            stats.first_seen["rust"].to_rfc3339(),
+            "2020-01-07T10:30:00+00:00"
+        );
+        assert_eq!(stats.multi_language, 1);
+        assert_eq!(stats.languages_per_challenge[&1], 5);
+        assert_eq!(stats.longest_streak.days, 3);
+        assert_eq!(
+            stats.longest_streak.start,
+            NaiveDate::from_ymd_opt(2020, 1, 6)
+        );
+        assert_eq!(stats.current_streak.days, 2);
+
+        let later = UserStats::at(&challenges, NaiveDate::from_ymd_opt(2020, 2, 10).unwrap());
+        assert_eq!(later.current_streak.days, 0);
+
+        let table = stats.to_table();
+        assert!(table.starts_with("Completed: 6 (1 in more than one language)\n"));
+        assert!(table.contains("rust      3            2020-01-07"));
+        assert!(stats.to_json().unwrap().contains("\"longest_streak\""));
+    }
+}
@@ -0,0 +1,1125 @@
+//! A local SQLite store for users, code challenges and completions.
+//!
+//! Code challenges are stored in `katas` table. A kata referenced only by a completion
+//! gets a row with its id, name and slug; remaining columns are filled in when its
+//! details are saved, which sets `details_fetched_at`. Likewise a user referenced only
+//! by completions or authored challenges gets a row with the username; the profile is
+//! filled in when the user is saved, which sets `updated_at`.
+
+use crate::code_challenges::{CodeChallenge, CreatedBy, Rank, Unresolved};
+use crate::err::{Error, JsonError, StoreError};
+use crate::kata_ref::KataRef;
+use crate::snapshot::UserSnapshot;
+use crate::user::{OverallRank, User};
+use crate::user_challenges::{AuthoredChallenge, CompletedChallenge};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use snafu::ResultExt;
+use std::path::Path;
+
+/// Schema migrations, applied in order. Index of a migration plus one is stored in
+/// `PRAGMA user_version` once it is applied.
+const MIGRATIONS: &[&str] = &[
+    r#"
+    CREATE TABLE languages (
+        name TEXT PRIMARY KEY
+    );
+
+    CREATE TABLE users (
+        username TEXT PRIMARY KEY,
+        name TEXT NOT NULL DEFAULT '',
+        honor INTEGER,
+        clan TEXT,
+        leaderboard_position INTEGER,
+        total_authored INTEGER NOT NULL DEFAULT 0,
+        total_completed INTEGER NOT NULL DEFAULT 0,
+        overall_rank INTEGER NOT NULL DEFAULT 0,
+        overall_rank_name TEXT NOT NULL DEFAULT '',
+        overall_rank_color TEXT NOT NULL DEFAULT '',
+        overall_score INTEGER NOT NULL DEFAULT 0,
+        updated_at TEXT
+    );
+
+    CREATE TABLE user_skills (
+        username TEXT NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+        skill TEXT NOT NULL,
+        PRIMARY KEY (username, skill)
+    );
+
+    CREATE TABLE user_language_ranks (
+        username TEXT NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+        language TEXT NOT NULL REFERENCES languages(name),
+        rank INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        color TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        PRIMARY KEY (username, language)
+    );
+
+    CREATE TABLE katas (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        slug TEXT,
+        category TEXT,
+        description TEXT,
+        url TEXT,
+        rank_id INTEGER,
+        rank_name TEXT,
+        rank_color TEXT,
+        published_at TEXT,
+        approved_at TEXT,
+        created_at TEXT,
+        created_by_username TEXT,
+        created_by_url TEXT,
+        approved_by_username TEXT,
+        approved_by_url TEXT,
+        total_attempts INTEGER,
+        total_completed INTEGER,
+        total_stars INTEGER,
+        vote_score INTEGER,
+        contributors_wanted INTEGER,
+        unresolved_issues INTEGER,
+        unresolved_suggestions INTEGER,
+        details_fetched_at TEXT
+    );
+
+    CREATE INDEX katas_slug ON katas(slug);
+
+    CREATE TABLE kata_languages (
+        kata_id TEXT NOT NULL REFERENCES katas(id) ON DELETE CASCADE,
+        language TEXT NOT NULL REFERENCES languages(name),
+        PRIMARY KEY (kata_id, language)
+    );
+
+    CREATE TABLE kata_tags (
+        kata_id TEXT NOT NULL REFERENCES katas(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (kata_id, tag)
+    );
+
+    CREATE TABLE completions (
+        username TEXT NOT NULL REFERENCES users(username),
+        kata_id TEXT NOT NULL REFERENCES katas(id),
+        completed_at TEXT NOT NULL,
+        PRIMARY KEY (username, kata_id)
+    );
+
+    CREATE INDEX completions_completed_at ON completions(username, completed_at);
+
+    CREATE TABLE completion_languages (
+        username TEXT NOT NULL,
+        kata_id TEXT NOT NULL,
+        language TEXT NOT NULL REFERENCES languages(name),
+        PRIMARY KEY (username, kata_id, language),
+        FOREIGN KEY (username, kata_id) REFERENCES completions(username, kata_id)
+            ON DELETE CASCADE
+    );
+
+    CREATE TABLE authored (
+        username TEXT NOT NULL REFERENCES users(username),
+        kata_id TEXT NOT NULL REFERENCES katas(id),
+        PRIMARY KEY (username, kata_id)
+    );
+"#,
+    r#"
+    CREATE TABLE sync_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        username TEXT NOT NULL,
+        started_at TEXT NOT NULL,
+        finished_at TEXT,
+        status TEXT NOT NULL,
+        new_completions INTEGER NOT NULL DEFAULT 0,
+        hydrated INTEGER NOT NULL DEFAULT 0,
+        history_complete INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE INDEX sync_log_username ON sync_log(username, started_at);
+
+    CREATE TABLE sync_errors (
+        sync_id INTEGER NOT NULL REFERENCES sync_log(id) ON DELETE CASCADE,
+        message TEXT NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE user_snapshots (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        username TEXT NOT NULL,
+        taken_at TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+
+    CREATE INDEX user_snapshots_taken_at ON user_snapshots(username, taken_at);
+"#,
+];
+
+/// A structure that holds a single entry of the sync log.
+#[derive(Debug, Clone)]
+pub struct SyncLogEntry {
+    pub id: i64,
+    pub username: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub status: String,
+    pub new_completions: u64,
+    pub hydrated: u64,
+    /// Whether every completed challenge of the user was stored once the sync finished.
+    pub history_complete: bool,
+    pub errors: Vec<String>,
+}
+
+/// Current time formatted the way Codewars formats timestamps.
+const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%SZ', 'now')";
+
+/// A local SQLite database of Codewars data.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens (or creates) a database file and brings its schema up to date.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let conn = Connection::open(path).context(StoreError)?;
+        Self::from_connection(conn)
+    }
+
+    /// Opens a database that lives only in memory. Useful for tests.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        let conn = Connection::open_in_memory().context(StoreError)?;
+        Self::from_connection(conn)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, Error> {
+        conn.pragma_update(None, "foreign_keys", true)
+            .context(StoreError)?;
+        let mut store = Store { conn };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Applies migrations that weren't applied yet.
+    fn migrate(&mut self) -> Result<(), Error> {
+        let version: usize = self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .context(StoreError)?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction().context(StoreError)?;
+            tx.execute_batch(migration).context(StoreError)?;
+            tx.pragma_update(None, "user_version", index + 1)
+                .context(StoreError)?;
+            tx.commit().context(StoreError)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the underlying connection, e.g. to run custom SQL queries.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Returns schema version of the database.
+    pub fn schema_version(&self) -> Result<usize, Error> {
+        self.conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .context(StoreError)
+    }
+
+    /// Inserts or replaces a user together with skills and language ranks.
+    pub fn save_user(&mut self, user: &User) -> Result<(), Error> {
+        let tx = self.conn.transaction().context(StoreError)?;
+        tx.execute(
+            &format!(
+                "INSERT INTO users (username, name, honor, clan, leaderboard_position,
+                    total_authored, total_completed, overall_rank, overall_rank_name,
+                    overall_rank_color, overall_score, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, {})
+                 ON CONFLICT(username) DO UPDATE SET
+                    name = excluded.name, honor = excluded.honor, clan = excluded.clan,
+                    leaderboard_position = excluded.leaderboard_position,
+                    total_authored = excluded.total_authored,
+                    total_completed = excluded.total_completed,
+                    overall_rank = excluded.overall_rank,
+                    overall_rank_name = excluded.overall_rank_name,
+                    overall_rank_color = excluded.overall_rank_color,
+                    overall_score = excluded.overall_score,
+                    updated_at = excluded.updated_at",
+                NOW
+            ),
+            params![
+                user.username,
+                user.name,
+                user.honor,
+                user.clan,
+                user.leaderboard_position,
+                user.code_challenges.total_authored as i64,
+                user.code_challenges.total_completed as i64,
+                user.ranks.overall.rank,
+                user.ranks.overall.name,
+                user.ranks.overall.color,
+                user.ranks.overall.score as i64,
+            ],
+        )
+        .context(StoreError)?;
+
+        tx.execute(
+            "DELETE FROM user_skills WHERE username = ?1",
+            params![user.username],
+        )
+        .context(StoreError)?;
+        for skill in &user.skills {
+            tx.execute(
+                "INSERT OR IGNORE INTO user_skills (username, skill) VALUES (?1, ?2)",
+                params![user.username, skill],
+            )
+            .context(StoreError)?;
+        }
+
+        tx.execute(
+            "DELETE FROM user_language_ranks WHERE username = ?1",
+            params![user.username],
+        )
+        .context(StoreError)?;
+        for (language, rank) in &user.ranks.languages {
+            insert_language(&tx, language)?;
+            tx.execute(
+                "INSERT INTO user_language_ranks (username, language, rank, name, color, score)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    user.username,
+                    language,
+                    rank.rank,
+                    rank.name,
+                    rank.color,
+                    rank.score as i64
+                ],
+            )
+            .context(StoreError)?;
+        }
+        tx.commit().context(StoreError)
+    }
+
+    /// Returns a stored user.
+    pub fn user(&self, username: &str) -> Result<Option<User>, Error> {
+        let user = self
+            .conn
+            .query_row(
+                "SELECT username, name, honor, clan, leaderboard_position, total_authored,
+                    total_completed, overall_rank, overall_rank_name, overall_rank_color,
+                    overall_score
+                 FROM users WHERE username = ?1 AND updated_at IS NOT NULL",
+                params![username],
+                |row| {
+                    let mut user = User::new();
+                    user.username = row.get(0)?;
+                    user.name = row.get(1)?;
+                    user.honor = row.get(2)?;
+                    user.clan = row.get(3)?;
+                    user.leaderboard_position = row.get(4)?;
+                    user.code_challenges.total_authored = row.get::<_, i64>(5)? as u64;
+                    user.code_challenges.total_completed = row.get::<_, i64>(6)? as u64;
+                    user.ranks.overall.rank = row.get(7)?;
+                    user.ranks.overall.name = row.get(8)?;
+                    user.ranks.overall.color = row.get(9)?;
+                    user.ranks.overall.score = row.get::<_, i64>(10)? as u64;
+                    Ok(user)
+                },
+            )
+            .optional()
+            .context(StoreError)?;
+        let mut user = match user {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        user.skills = self.strings(
+            "SELECT skill FROM user_skills WHERE username = ?1 ORDER BY skill",
+            username,
+        )?;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT language, rank, name, color, score FROM user_language_ranks
+                 WHERE username = ?1",
+            )
+            .context(StoreError)?;
+        let ranks = stmt
+            .query_map(params![username], |row| {
+                let mut rank = OverallRank::new();
+                rank.rank = row.get(1)?;
+                rank.name = row.get(2)?;
+                rank.color = row.get(3)?;
+                rank.score = row.get::<_, i64>(4)? as u64;
+                Ok((row.get::<_, String>(0)?, rank))
+            })
+            .context(StoreError)?;
+        for rank in ranks {
+            let (language, rank) = rank.context(StoreError)?;
+            user.ranks.languages.insert(language, rank);
+        }
+        Ok(Some(user))
+    }
+
+    /// Returns usernames of all stored users, including users whose profile isn't stored
+    /// yet.
+    pub fn usernames(&self) -> Result<Vec<String>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT username FROM users ORDER BY username")
+            .context(StoreError)?;
+        let rows = stmt.query_map([], |row| row.get(0)).context(StoreError)?;
+        rows.collect::<Result<_, _>>().context(StoreError)
+    }
+
+    /// Inserts or replaces full details of a code challenge.
+    pub fn save_code_challenge(&mut self, challenge: &CodeChallenge) -> Result<(), Error> {
+        let tx = self.conn.transaction().context(StoreError)?;
+        tx.execute(
+            &format!(
+                "INSERT INTO katas (id, name, slug, category, description, url, rank_id,
+                    rank_name, rank_color, published_at, approved_at, created_at,
+                    created_by_username, created_by_url, approved_by_username,
+                    approved_by_url, total_attempts, total_completed, total_stars,
+                    vote_score, contributors_wanted, unresolved_issues,
+                    unresolved_suggestions, details_fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+                    ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, {})
+                 ON CONFLICT(id) DO UPDATE SET
+                    name = excluded.name, slug = excluded.slug,
+                    category = excluded.category, description = excluded.description,
+                    url = excluded.url, rank_id = excluded.rank_id,
+                    rank_name = excluded.rank_name, rank_color = excluded.rank_color,
+                    published_at = excluded.published_at,
+                    approved_at = excluded.approved_at, created_at = excluded.created_at,
+                    created_by_username = excluded.created_by_username,
+                    created_by_url = excluded.created_by_url,
+                    approved_by_username = excluded.approved_by_username,
+                    approved_by_url = excluded.approved_by_url,
+                    total_attempts = excluded.total_attempts,
+                    total_completed = excluded.total_completed,
+                    total_stars = excluded.total_stars, vote_score = excluded.vote_score,
+                    contributors_wanted = excluded.contributors_wanted,
+                    unresolved_issues = excluded.unresolved_issues,
+                    unresolved_suggestions = excluded.unresolved_suggestions,
+                    details_fetched_at = excluded.details_fetched_at",
+                NOW
+            ),
+            params![
+                challenge.id,
+                challenge.name,
+                challenge.slug,
+                challenge.category,
+                challenge.description,
+                challenge.url,
+                challenge.rank.id,
+                challenge.rank.name,
+                challenge.rank.color,
+                challenge.published_at,
+                challenge.approved_at,
+                challenge.created_at,
+                challenge.created_by.username,
+                challenge.created_by.url,
+                challenge.approved_by.username,
+                challenge.approved_by.url,
+                challenge.total_attempts as i64,
+                challenge.total_completed as i64,
+                challenge.total_stars as i64,
+                challenge.vote_score as i64,
+                challenge.contributors_wanted,
+                challenge.unresolved.issues as i64,
+                challenge.unresolved.suggestions as i64,
+            ],
+        )
+        .context(StoreError)?;
+        replace_kata_lists(&tx, &challenge.id, &challenge.languages, &challenge.tags)?;
+        tx.commit().context(StoreError)
+    }
+
+    /// Returns a code challenge by id, slug or kata URL, if its details were saved.
+    pub fn code_challenge(&self, id_or_slug: &str) -> Result<Option<CodeChallenge>, Error> {
+        let id_or_slug = KataRef::parse(id_or_slug)?.id_or_slug;
+        let challenge = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM katas
+                     WHERE (id = ?1 OR slug = ?1) AND details_fetched_at IS NOT NULL",
+                    KATA_COLUMNS
+                ),
+                params![id_or_slug],
+                kata_from_row,
+            )
+            .optional()
+            .context(StoreError)?;
+        match challenge {
+            Some(mut challenge) => {
+                self.fill_kata_lists(&mut challenge)?;
+                Ok(Some(challenge))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns all code challenges whose details were saved, ordered by name.
+    pub fn code_challenges(&self) -> Result<Vec<CodeChallenge>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM katas WHERE details_fetched_at IS NOT NULL ORDER BY name, id",
+                KATA_COLUMNS
+            ))
+            .context(StoreError)?;
+        let rows = stmt.query_map([], kata_from_row).context(StoreError)?;
+        let mut challenges: Vec<CodeChallenge> =
+            rows.collect::<Result<_, _>>().context(StoreError)?;
+        for challenge in challenges.iter_mut() {
+            self.fill_kata_lists(challenge)?;
+        }
+        Ok(challenges)
+    }
+
+    /// Returns ids of katas that are referenced by completions or authored challenges but
+    /// whose details weren't saved yet.
+    pub fn katas_missing_details(&self) -> Result<Vec<String>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM katas WHERE details_fetched_at IS NULL ORDER BY id")
+            .context(StoreError)?;
+        let rows = stmt.query_map([], |row| row.get(0)).context(StoreError)?;
+        rows.collect::<Result<_, _>>().context(StoreError)
+    }
+
+    /// Inserts or updates completed challenges of a user. Returns number of completions
+    /// that weren't stored before.
+    pub fn save_completed_challenges(
+        &mut self,
+        username: &str,
+        challenges: &[CompletedChallenge],
+    ) -> Result<usize, Error> {
+        let tx = self.conn.transaction().context(StoreError)?;
+        insert_user_stub(&tx, username)?;
+        let mut inserted = 0;
+        for challenge in challenges {
+            insert_kata_stub(&tx, &challenge.id, &challenge.name, &challenge.slug)?;
+            inserted += tx
+                .execute(
+                    "INSERT OR IGNORE INTO completions (username, kata_id, completed_at)
+                     VALUES (?1, ?2, ?3)",
+                    params![username, challenge.id, challenge.completed_at],
+                )
+                .context(StoreError)?;
+            tx.execute(
+                "UPDATE completions SET completed_at = ?3 WHERE username = ?1 AND kata_id = ?2",
+                params![username, challenge.id, challenge.completed_at],
+            )
+            .context(StoreError)?;
+            tx.execute(
+                "DELETE FROM completion_languages WHERE username = ?1 AND kata_id = ?2",
+                params![username, challenge.id],
+            )
+            .context(StoreError)?;
+            for language in &challenge.completed_languages {
+                insert_language(&tx, language)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO completion_languages (username, kata_id, language)
+                     VALUES (?1, ?2, ?3)",
+                    params![username, challenge.id, language],
+                )
+                .context(StoreError)?;
+            }
+        }
+        tx.commit().context(StoreError)?;
+        Ok(inserted)
+    }
+
+    /// Returns completed challenges of a user, most recent first.
+    pub fn completed_challenges(&self, username: &str) -> Result<Vec<CompletedChallenge>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT k.id, k.name, k.slug, c.completed_at FROM completions c
+                 JOIN katas k ON k.id = c.kata_id
+                 WHERE c.username = ?1
+                 ORDER BY c.completed_at DESC, k.id",
+            )
+            .context(StoreError)?;
+        let rows = stmt
+            .query_map(params![username], |row| {
+                let mut challenge = CompletedChallenge::new();
+                challenge.id = row.get(0)?;
+                challenge.name = row.get(1)?;
+                challenge.slug = row.get::<_, Option<String>>(2)?.unwrap_or_default();
+                challenge.completed_at = row.get(3)?;
+                Ok(challenge)
+            })
+            .context(StoreError)?;
+        let mut challenges: Vec<CompletedChallenge> =
+            rows.collect::<Result<_, _>>().context(StoreError)?;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT language FROM completion_languages
+                 WHERE username = ?1 AND kata_id = ?2 ORDER BY language",
+            )
+            .context(StoreError)?;
+        for challenge in challenges.iter_mut() {
+            let languages = stmt
+                .query_map(params![username, challenge.id], |row| row.get(0))
+                .context(StoreError)?;
+            challenge.completed_languages =
+                languages.collect::<Result<_, _>>().context(StoreError)?;
+        }
+        Ok(challenges)
+    }
+
+    /// Inserts or updates challenges authored by a user.
+    pub fn save_authored_challenges(
+        &mut self,
+        username: &str,
+        challenges: &[AuthoredChallenge],
+    ) -> Result<(), Error> {
+        let tx = self.conn.transaction().context(StoreError)?;
+        insert_user_stub(&tx, username)?;
+        for challenge in challenges {
+            tx.execute(
+                "INSERT INTO katas (id, name, description, rank_id, rank_name)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(id) DO UPDATE SET
+                    name = excluded.name, description = excluded.description,
+                    rank_id = excluded.rank_id, rank_name = excluded.rank_name",
+                params![
+                    challenge.id,
+                    challenge.name,
+                    challenge.description,
+                    challenge.rank,
+                    challenge.rank_name
+                ],
+            )
+            .context(StoreError)?;
+            replace_kata_lists(&tx, &challenge.id, &challenge.languages, &challenge.tags)?;
+            tx.execute(
+                "INSERT OR IGNORE INTO authored (username, kata_id) VALUES (?1, ?2)",
+                params![username, challenge.id],
+            )
+            .context(StoreError)?;
+        }
+        tx.commit().context(StoreError)
+    }
+
+    /// Returns challenges authored by a user.
+    pub fn authored_challenges(&self, username: &str) -> Result<Vec<AuthoredChallenge>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT k.id, k.name, k.description, k.rank_id, k.rank_name FROM authored a
+                 JOIN katas k ON k.id = a.kata_id
+                 WHERE a.username = ?1
+                 ORDER BY k.name, k.id",
+            )
+            .context(StoreError)?;
+        let rows = stmt
+            .query_map(params![username], |row| {
+                let mut challenge = AuthoredChallenge::new();
+                challenge.id = row.get(0)?;
+                challenge.name = row.get(1)?;
+                challenge.description = row.get::<_, Option<String>>(2)?.unwrap_or_default();
+                challenge.rank = row.get::<_, Option<i64>>(3)?.unwrap_or_default();
+                challenge.rank_name = row.get::<_, Option<String>>(4)?.unwrap_or_default();
+                Ok(challenge)
+            })
+            .context(StoreError)?;
+        let mut challenges: Vec<AuthoredChallenge> =
+            rows.collect::<Result<_, _>>().context(StoreError)?;
+        for challenge in challenges.iter_mut() {
+            challenge.languages = self.strings(
+                "SELECT language FROM kata_languages WHERE kata_id = ?1 ORDER BY language",
+                &challenge.id,
+            )?;
+            challenge.tags = self.strings(
+                "SELECT tag FROM kata_tags WHERE kata_id = ?1 ORDER BY tag",
+                &challenge.id,
+            )?;
+        }
+        Ok(challenges)
+    }
+
+    /// Returns number of katas a user completed in each language, most used first.
+    pub fn completions_per_language(&self, username: &str) -> Result<Vec<(String, u64)>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT language, COUNT(*) AS total FROM completion_languages
+                 WHERE username = ?1
+                 GROUP BY language
+                 ORDER BY total DESC, language",
+            )
+            .context(StoreError)?;
+        let rows = stmt
+            .query_map(params![username], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+            })
+            .context(StoreError)?;
+        rows.collect::<Result<_, _>>().context(StoreError)
+    }
+
+    /// Returns ids of katas completed or authored by a user whose details weren't saved yet.
+    pub fn user_katas_missing_details(&self, username: &str) -> Result<Vec<String>, Error> {
+        self.strings(
+            "SELECT id FROM katas WHERE details_fetched_at IS NULL AND id IN (
+                SELECT kata_id FROM completions WHERE username = ?1
+                UNION SELECT kata_id FROM authored WHERE username = ?1)
+             ORDER BY id",
+            username,
+        )
+    }
+
+    /// Returns true if a completion with the same kata and time is already stored.
+    pub fn has_completion(
+        &self,
+        username: &str,
+        challenge: &CompletedChallenge,
+    ) -> Result<bool, Error> {
+        self.conn
+            .query_row(
+                "SELECT 1 FROM completions
+                 WHERE username = ?1 AND kata_id = ?2 AND completed_at = ?3",
+                params![username, challenge.id, challenge.completed_at],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|row| row.is_some())
+            .context(StoreError)
+    }
+
+    /// Adds a running entry to the sync log and returns its id.
+    pub fn start_sync(&mut self, username: &str) -> Result<i64, Error> {
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO sync_log (username, started_at, status)
+                     VALUES (?1, {}, 'running')",
+                    NOW
+                ),
+                params![username],
+            )
+            .context(StoreError)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Marks an entry of the sync log as finished with given status and errors.
+    pub fn finish_sync(
+        &mut self,
+        sync_id: i64,
+        status: &str,
+        new_completions: u64,
+        hydrated: u64,
+        errors: &[String],
+    ) -> Result<(), Error> {
+        let tx = self.conn.transaction().context(StoreError)?;
+        tx.execute(
+            &format!(
+                "UPDATE sync_log SET finished_at = {}, status = ?2, new_completions = ?3,
+                    hydrated = ?4
+                 WHERE id = ?1",
+                NOW
+            ),
+            params![sync_id, status, new_completions as i64, hydrated as i64],
+        )
+        .context(StoreError)?;
+        for error in errors {
+            tx.execute(
+                "INSERT INTO sync_errors (sync_id, message) VALUES (?1, ?2)",
+                params![sync_id, error],
+            )
+            .context(StoreError)?;
+        }
+        tx.commit().context(StoreError)
+    }
+
+    /// Records that a sync has stored every completed challenge of its user.
+    pub fn mark_history_complete(&mut self, sync_id: i64) -> Result<(), Error> {
+        self.conn
+            .execute(
+                "UPDATE sync_log SET history_complete = 1 WHERE id = ?1",
+                params![sync_id],
+            )
+            .context(StoreError)?;
+        Ok(())
+    }
+
+    /// Returns whether the sync run before `sync_id` left every completed challenge of
+    /// the user stored. A sync that failed or was interrupted while paging doesn't.
+    pub fn history_complete_before(&self, username: &str, sync_id: i64) -> Result<bool, Error> {
+        self.conn
+            .query_row(
+                "SELECT history_complete FROM sync_log WHERE username = ?1 AND id < ?2
+                 ORDER BY id DESC LIMIT 1",
+                params![username, sync_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .context(StoreError)
+            .map(|complete| complete.unwrap_or(false))
+    }
+
+    /// Returns sync log of a user, most recent first.
+    pub fn sync_log(&self, username: &str) -> Result<Vec<SyncLogEntry>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, username, started_at, finished_at, status, new_completions, hydrated,
+                    history_complete
+                 FROM sync_log WHERE username = ?1 ORDER BY id DESC",
+            )
+            .context(StoreError)?;
+        let rows = stmt
+            .query_map(params![username], |row| {
+                Ok(SyncLogEntry {
+                    id: row.get(0)?,
+                    username: row.get(1)?,
+                    started_at: row.get(2)?,
+                    finished_at: row.get(3)?,
+                    status: row.get(4)?,
+                    new_completions: row.get::<_, i64>(5)? as u64,
+                    hydrated: row.get::<_, i64>(6)? as u64,
+                    history_complete: row.get(7)?,
+                    errors: vec![],
+                })
+            })
+            .context(StoreError)?;
+        let mut entries: Vec<SyncLogEntry> = rows.collect::<Result<_, _>>().context(StoreError)?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT message FROM sync_errors WHERE sync_id = ?1 ORDER BY rowid")
+            .context(StoreError)?;
+        for entry in entries.iter_mut() {
+            let errors = stmt
+                .query_map(params![entry.id], |row| row.get(0))
+                .context(StoreError)?;
+            entry.errors = errors.collect::<Result<_, _>>().context(StoreError)?;
+        }
+        Ok(entries)
+    }
+
+    /// Stores a snapshot of a user.
+    pub fn save_snapshot(&mut self, snapshot: &UserSnapshot) -> Result<(), Error> {
+        let data = serde_json::to_string(snapshot).context(JsonError)?;
+        self.conn
+            .execute(
+                "INSERT INTO user_snapshots (username, taken_at, data) VALUES (?1, ?2, ?3)",
+                params![
+                    snapshot.user.username,
+                    format_time(&snapshot.taken_at),
+                    data
+                ],
+            )
+            .context(StoreError)?;
+        Ok(())
+    }
+
+    /// Returns all snapshots of a user, oldest first.
+    pub fn snapshots(&self, username: &str) -> Result<Vec<UserSnapshot>, Error> {
+        let data = self.strings(
+            "SELECT data FROM user_snapshots WHERE username = ?1 ORDER BY taken_at, id",
+            username,
+        )?;
+        data.iter()
+            .map(|data| serde_json::from_str(data).context(JsonError))
+            .collect()
+    }
+
+    /// Returns the latest snapshot of a user taken at or before given time.
+    pub fn snapshot_at(
+        &self,
+        username: &str,
+        at: &DateTime<Utc>,
+    ) -> Result<Option<UserSnapshot>, Error> {
+        let data: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT data FROM user_snapshots WHERE username = ?1 AND taken_at <= ?2
+                 ORDER BY taken_at DESC, id DESC LIMIT 1",
+                params![username, format_time(at)],
+                |row| row.get(0),
+            )
+            .optional()
+            .context(StoreError)?;
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data).context(JsonError)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns results of a query that selects a single text column filtered by one parameter.
+    fn strings(&self, sql: &str, param: &str) -> Result<Vec<String>, Error> {
+        let mut stmt = self.conn.prepare(sql).context(StoreError)?;
+        let rows = stmt
+            .query_map(params![param], |row| row.get(0))
+            .context(StoreError)?;
+        rows.collect::<Result<_, _>>().context(StoreError)
+    }
+
+    /// Fills up languages and tags of a code challenge read from `katas` table.
+    fn fill_kata_lists(&self, challenge: &mut CodeChallenge) -> Result<(), Error> {
+        challenge.languages = self.strings(
+            "SELECT language FROM kata_languages WHERE kata_id = ?1 ORDER BY language",
+            &challenge.id,
+        )?;
+        challenge.tags = self.strings(
+            "SELECT tag FROM kata_tags WHERE kata_id = ?1 ORDER BY tag",
+            &challenge.id,
+        )?;
+        Ok(())
+    }
+}
+
+/// Columns of `katas` table read by `kata_from_row`.
+const KATA_COLUMNS: &str = "id, name, slug, category, description, url, rank_id, rank_name,
+    rank_color, published_at, approved_at, created_at, created_by_username, created_by_url,
+    approved_by_username, approved_by_url, total_attempts, total_completed, total_stars,
+    vote_score, contributors_wanted, unresolved_issues, unresolved_suggestions";
+
+/// Builds a code challenge from a row selected with `KATA_COLUMNS`.
+fn kata_from_row(row: &Row) -> rusqlite::Result<CodeChallenge> {
+    let text = |index: usize| -> rusqlite::Result<String> {
+        Ok(row.get::<_, Option<String>>(index)?.unwrap_or_default())
+    };
+    let number = |index: usize| -> rusqlite::Result<i64> {
+        Ok(row.get::<_, Option<i64>>(index)?.unwrap_or_default())
+    };
+
+    let mut challenge = CodeChallenge::new();
+    challenge.id = text(0)?;
+    challenge.name = text(1)?;
+    challenge.slug = text(2)?;
+    challenge.category = text(3)?;
+    challenge.description = text(4)?;
+    challenge.url = text(5)?;
+    let mut rank = Rank::new();
+    rank.id = number(6)?;
+    rank.name = text(7)?;
+    rank.color = text(8)?;
+    challenge.rank = rank;
+    challenge.published_at = text(9)?;
+    challenge.approved_at = text(10)?;
+    challenge.created_at = text(11)?;
+    let mut created_by = CreatedBy::new();
+    created_by.username = text(12)?;
+    created_by.url = text(13)?;
+    challenge.created_by = created_by;
+    let mut approved_by = CreatedBy::new();
+    approved_by.username = text(14)?;
+    approved_by.url = text(15)?;
+    challenge.approved_by = approved_by;
+    challenge.total_attempts = number(16)? as u64;
+    challenge.total_completed = number(17)? as u64;
+    challenge.total_stars = number(18)? as u64;
+    challenge.vote_score = number(19)? as u64;
+    challenge.contributors_wanted = number(20)? != 0;
+    let mut unresolved = Unresolved::new();
+    unresolved.issues = number(21)? as u64;
+    unresolved.suggestions = number(22)? as u64;
+    challenge.unresolved = unresolved;
+    Ok(challenge)
+}
+
+/// Formats time the way it is stored, so stored times sort chronologically as text.
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn insert_language(conn: &Connection, language: &str) -> Result<(), Error> {
+    conn.execute(
+        "INSERT OR IGNORE INTO languages (name) VALUES (?1)",
+        params![language],
+    )
+    .context(StoreError)?;
+    Ok(())
+}
+
+/// Makes sure a kata row exists without overwriting details saved earlier.
+fn insert_kata_stub(conn: &Connection, id: &str, name: &str, slug: &str) -> Result<(), Error> {
+    conn.execute(
+        "INSERT INTO katas (id, name, slug) VALUES (?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, slug = excluded.slug",
+        params![id, name, slug],
+    )
+    .context(StoreError)?;
+    Ok(())
+}
+
+/// Inserts a user by username only, unless the user is already stored.
+fn insert_user_stub(conn: &Connection, username: &str) -> Result<(), Error> {
+    conn.execute(
+        "INSERT OR IGNORE INTO users (username) VALUES (?1)",
+        params![username],
+    )
+    .context(StoreError)?;
+    Ok(())
+}
+
+/// Replaces languages and tags of a kata.
+fn replace_kata_lists(
+    conn: &Connection,
+    kata_id: &str,
+    languages: &[String],
+    tags: &[String],
+) -> Result<(), Error> {
+    conn.execute(
+        "DELETE FROM kata_languages WHERE kata_id = ?1",
+        params![kata_id],
+    )
+    .context(StoreError)?;
+    for language in languages {
+        insert_language(conn, language)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO kata_languages (kata_id, language) VALUES (?1, ?2)",
+            params![kata_id, language],
+        )
+        .context(StoreError)?;
+    }
+    conn.execute("DELETE FROM kata_tags WHERE kata_id = ?1", params![kata_id])
+        .context(StoreError)?;
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO kata_tags (kata_id, tag) VALUES (?1, ?2)",
+            params![kata_id, tag],
+        )
+        .context(StoreError)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Store;
+    use crate::code_challenges::CodeChallenge;
+    use crate::user::{OverallRank, User};
+    use crate::user_challenges::{AuthoredChallenge, CompletedChallenge};
+
+    fn completed(id: &str, completed_at: &str, languages: &[&str]) -> CompletedChallenge {
+        let mut challenge = CompletedChallenge::new();
+        challenge.id = id.to_string();
+        challenge.name = format!("Kata {}", id);
+        challenge.slug = format!("kata-{}", id);
+        challenge.completed_at = completed_at.to_string();
+        challenge.completed_languages = languages.iter().map(|l| l.to_string()).collect();
+        challenge
+    }
+
+    #[test]
+    fn test_user_round_trip() {
+        let mut store = Store::open_in_memory().unwrap();
+        let mut user = User::new();
+        user.username = "some_user".to_string();
+        user.name = "Some Person".to_string();
+        user.honor = Some(544);
+        user.skills = vec!["ruby".to_string()];
+        let mut rank = OverallRank::new();
+        rank.rank = -4;
+        rank.name = "4 kyu".to_string();
+        user.ranks.languages.insert("ruby".to_string(), rank);
+        store.save_user(&user).unwrap();
+        store.save_user(&user).unwrap();
+
+        let stored = store.user("some_user").unwrap().unwrap();
+        assert_eq!(stored.honor, Some(544));
+        assert_eq!(stored.skills, vec!["ruby".to_string()]);
+        assert_eq!(stored.ranks.languages["ruby"].name, "4 kyu".to_string());
+        assert!(store.user("nobody").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_completions_and_katas() {
+        let mut store = Store::open_in_memory().unwrap();
+        let challenges = vec![
+            completed("a", "2020-02-01T10:00:00Z", &["rust", "python"]),
+            completed("b", "2020-01-01T10:00:00Z", &["rust"]),
+        ];
+        assert_eq!(
+            store
+                .save_completed_challenges("some_user", &challenges)
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            store
+                .save_completed_challenges("some_user", &challenges)
+                .unwrap(),
+            0
+        );
+        assert_eq!(store.katas_missing_details().unwrap(), vec!["a", "b"]);
+
+        let mut kata = CodeChallenge::new();
+        kata.id = "a".to_string();
+        kata.slug = "kata-a".to_string();
+        kata.name = "Kata a".to_string();
+        kata.tags = vec!["Algorithms".to_string()];
+        kata.languages = vec!["rust".to_string()];
+        store.save_code_challenge(&kata).unwrap();
+        assert_eq!(store.katas_missing_details().unwrap(), vec!["b"]);
+        assert_eq!(
+            store.code_challenge("kata-a").unwrap().unwrap().tags.len(),
+            1
+        );
+
+        let stored = store.completed_challenges("some_user").unwrap();
+        assert_eq!(stored[0].id, "a".to_string());
+        assert_eq!(stored[0].completed_languages, vec!["python", "rust"]);
+        assert_eq!(
+            store.completions_per_language("some_user").unwrap()[0],
+            ("rust".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn test_stub_users_and_authored_slugs() {
+        let mut store = Store::open_in_memory().unwrap();
+        let mut authored = AuthoredChallenge::new();
+        authored.id = "x".to_string();
+        authored.name = "Kata x".to_string();
+        store
+            .save_authored_challenges("author", &[authored])
+            .unwrap();
+        store
+            .save_completed_challenges("solver", &[completed("x", "2020-01-01T10:00:00Z", &[])])
+            .unwrap();
+
+        // Users referenced only by challenges have no profile yet
+        assert!(store.user("author").unwrap().is_none());
+        assert_eq!(store.usernames().unwrap(), vec!["author", "solver"]);
+        let slug: Option<String> = store
+            .connection()
+            .query_row("SELECT slug FROM katas WHERE id = 'x'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(slug, Some("kata-x".to_string()));
+
+        let mut authored = AuthoredChallenge::new();
+        authored.id = "y".to_string();
+        store
+            .save_authored_challenges("author", &[authored])
+            .unwrap();
+        let slug: Option<String> = store
+            .connection()
+            .query_row("SELECT slug FROM katas WHERE id = 'y'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(slug, None);
+
+        assert!(store
+            .connection()
+            .execute(
+                "INSERT INTO completions (username, kata_id, completed_at)
+                 VALUES ('nobody', 'x', '2020-01-01T10:00:00Z')",
+                [],
+            )
+            .is_err());
+    }
+
+}
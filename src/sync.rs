@@ -0,0 +1,196 @@
+//! Mirroring of a user's Codewars history into the local store.
+
+use crate::codewars::Codewars;
+use crate::err::Error;
+use crate::snapshot::UserSnapshot;
+use crate::store::Store;
+
+/// A structure that holds outcome of a single sync.
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    pub username: String,
+    pub pages_fetched: u64,
+    pub new_completions: u64,
+    pub authored: u64,
+    pub hydrated: u64,
+    pub errors: Vec<String>,
+}
+
+/// Mirrors profile (also stored as a snapshot), completed and authored challenges of a user into the store, and
+/// fetches details of katas that aren't stored yet.
+///
+/// Completed challenges are fetched page by page, most recent first. Once a previous
+/// sync has stored the whole history, paging stops at the first page that contains a
+/// completion that is already stored; otherwise every page is fetched. Katas whose
+/// details can't be fetched are recorded as errors and retried on the next sync. Every
+/// run is recorded in the sync log, including failed ones.
+pub fn sync_user(
+    codewars: &Codewars,
+    store: &mut Store,
+    username: &str,
+) -> Result<SyncReport, Error> {
+    let sync_id = store.start_sync(username)?;
+    let mut report = SyncReport {
+        username: username.to_string(),
+        ..SyncReport::default()
+    };
+    match run_sync(codewars, store, sync_id, &mut report) {
+        Ok(()) => {
+            let status = if report.errors.is_empty() {
+                "ok"
+            } else {
+                "partial"
+            };
+            store.finish_sync(
+                sync_id,
+                status,
+                report.new_completions,
+                report.hydrated,
+                &report.errors,
+            )?;
+            Ok(report)
+        }
+        Err(e) => {
+            report.errors.push(e.to_string());
+            store.finish_sync(
+                sync_id,
+                "failed",
+                report.new_completions,
+                report.hydrated,
+                &report.errors,
+            )?;
+            Err(e)
+        }
+    }
+}
+
+fn run_sync(
+    codewars: &Codewars,
+    store: &mut Store,
+    sync_id: i64,
+    report: &mut SyncReport,
+) -> Result<(), Error> {
+    let username = report.username.clone();
+    let history_complete = store.history_complete_before(&username, sync_id)?;
+
+    let user = codewars.get_user(username.clone())?;
+    store.save_user(&user)?;
+    store.save_snapshot(&UserSnapshot::new(user))?;
+
+    let mut page = 0;
+    loop {
+        let completed_page = codewars.get_completed_challenges_page(username.clone(), page)?;
+        report.pages_fetched += 1;
+
+        let mut reached_known = false;
+        if history_complete {
+            for challenge in &completed_page.data {
+                if store.has_completion(&username, challenge)? {
+                    reached_known = true;
+                    break;
+                }
+            }
+        }
+        report.new_completions +=
+            store.save_completed_challenges(&username, &completed_page.data)? as u64;
+
+        page += 1;
+        if reached_known || page >= completed_page.total_pages {
+            break;
+        }
+    }
+    store.mark_history_complete(sync_id)?;
+
+    let authored = codewars.get_authored_challenges(username.clone())?;
+    store.save_authored_challenges(&username, &authored)?;
+    report.authored = authored.len() as u64;
+
+    let missing = store.user_katas_missing_details(&username)?;
+    let ids: Vec<&str> = missing.iter().map(|id| id.as_str()).collect();
+    for (kata_id, result) in codewars.get_code_challenges(&ids) {
+        match result {
+            Ok(challenge) => {
+                store.save_code_challenge(&challenge)?;
+                report.hydrated += 1;
+            }
+            Err(e) => report.errors.push(format!("Kata {}: {}", kata_id, e)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::sync_user;
+    use crate::codewars::Codewars;
+    use crate::store::Store;
+    use codewars_mock::MockServerBuilder;
+    use std::path::Path;
+
+    #[test]
+    fn test_sync_is_incremental() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let server = MockServerBuilder::from_dir(&data_dir)
+            .unwrap()
+            .page_size(1)
+            .start()
+            .unwrap();
+        let codewars = Codewars::with_base_url("".to_string(), server.base_url());
+        let mut store = Store::open_in_memory().unwrap();
+
+        let report = sync_user(&codewars, &mut store, "some_user").unwrap();
+        assert_eq!(report.pages_fetched, 3);
+        assert_eq!(report.new_completions, 3);
+        // Only valid-braces is served by the mock
+        assert_eq!(report.hydrated, 1);
+        assert_eq!(report.errors.len(), 3);
+
+        let report = sync_user(&codewars, &mut store, "some_user").unwrap();
+        assert_eq!(report.pages_fetched, 1);
+        assert_eq!(report.new_completions, 0);
+        assert_eq!(report.hydrated, 0);
+
+        assert_eq!(store.snapshots("some_user").unwrap().len(), 2);
+
+        let log = store.sync_log("some_user").unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1].status, "partial".to_string());
+        assert_eq!(log[1].errors.len(), 3);
+        assert!(log[0].history_complete && log[1].history_complete);
+    }
+
+    #[test]
+    fn test_sync_after_failed_sync_fetches_every_page() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let server = MockServerBuilder::from_dir(&data_dir)
+            .unwrap()
+            .page_size(1)
+            .start()
+            .unwrap();
+        let codewars = Codewars::with_base_url("".to_string(), server.base_url());
+        let mut store = Store::open_in_memory().unwrap();
+
+        // A sync that stored the first page and failed on the second one
+        let sync_id = store.start_sync("some_user").unwrap();
+        let first_page = codewars
+            .get_completed_challenges_page("some_user".to_string(), 0)
+            .unwrap();
+        store
+            .save_completed_challenges("some_user", &first_page.data)
+            .unwrap();
+        store
+            .finish_sync(sync_id, "failed", 1, 0, &["page 1".to_string()])
+            .unwrap();
+
+        let report = sync_user(&codewars, &mut store, "some_user").unwrap();
+        assert_eq!(report.pages_fetched, 3);
+        assert_eq!(report.new_completions, 2);
+        assert_eq!(store.completed_challenges("some_user").unwrap().len(), 3);
+
+        let log = store.sync_log("some_user").unwrap();
+        assert_eq!(log[1].status, "failed".to_string());
+        assert!(!log[1].history_complete);
+        assert!(log[0].history_complete);
+    }
+}
@@ -0,0 +1,28 @@
+//! Plain text tables shared by the library and the command-line tool.
+
+/// Renders rows as a table with a header line, a line of dashes and columns aligned by
+/// character count. Trailing spaces are trimmed from every line.
+pub fn render(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|column| column.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: Vec<&str>| -> String {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = *width))
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    let mut lines = vec![line(header.to_vec()), separator.join("  ")];
+    for row in rows {
+        lines.push(line(row.iter().map(|cell| cell.as_str()).collect()));
+    }
+    lines.join("\n")
+}
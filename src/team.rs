@@ -0,0 +1,384 @@
+//! Leaderboards of a configured group of users.
+//!
+//! Codewars has clans but no API for them, so a team is just a list of usernames.
+//! Members are fetched in parallel and ranked by honor, overall score or score in a
+//! language. Given snapshots of the members from a week ago, the leaderboard also shows
+//! how values and positions changed since then.
+
+use crate::codewars::Codewars;
+use crate::err::{Error, IoError, JsonError};
+use crate::escape::escape_html;
+use crate::snapshot::UserSnapshot;
+#[cfg(feature = "store")]
+use crate::store::Store;
+use crate::user::User;
+#[cfg(feature = "store")]
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// What a leaderboard is ranked by.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SortBy {
+    Honor,
+    /// Overall rank score.
+    Score,
+    /// Rank score in a language. Members without a rank in it are left out.
+    Language(String),
+}
+
+impl SortBy {
+    fn value(&self, user: &User) -> Option<i64> {
+        match self {
+            SortBy::Honor => Some(user.honor.unwrap_or(0)),
+            SortBy::Score => Some(user.ranks.overall.score as i64),
+            SortBy::Language(language) => user
+                .ranks
+                .languages
+                .get(language)
+                .map(|rank| rank.score as i64),
+        }
+    }
+
+    fn rank_name(&self, user: &User) -> String {
+        match self {
+            SortBy::Language(language) => user
+                .ranks
+                .languages
+                .get(language)
+                .map(|rank| rank.name.clone())
+                .unwrap_or_default(),
+            _ => user.ranks.overall.name.clone(),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            SortBy::Honor => "Honor".to_string(),
+            SortBy::Score => "Score".to_string(),
+            SortBy::Language(language) => format!("{} score", language),
+        }
+    }
+}
+
+/// A named list of usernames.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Team {
+    pub name: String,
+    pub usernames: Vec<String>,
+}
+
+impl Team {
+    /// Returns a new team.
+    pub fn new(name: &str, usernames: Vec<String>) -> Self {
+        Team {
+            name: name.to_string(),
+            usernames,
+        }
+    }
+
+    /// Reads a team from a file with a username per line. Empty lines and lines starting
+    /// with `#` are skipped. The team is named after the file.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).context(IoError)?;
+        let usernames = content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.to_string())
+            .collect();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(Team { name, usernames })
+    }
+
+    /// Fetches all members in parallel, up to the concurrency and within the rate limit
+    /// of `codewars`. Returns users in the order of usernames and members that couldn't
+    /// be fetched, with the reason.
+    pub fn fetch(&self, codewars: &Codewars) -> (Vec<User>, Vec<(String, Error)>) {
+        let results = codewars.fan_out(&self.usernames, |username| {
+            (username.clone(), codewars.get_user(username.clone()))
+        });
+        let mut users = vec![];
+        let mut errors = vec![];
+        for (username, result) in results {
+            match result {
+                Ok(user) => users.push(user),
+                Err(e) => errors.push((username, e)),
+            }
+        }
+        (users, errors)
+    }
+
+    /// Returns the latest stored snapshot of every member taken at least a week before
+    /// `now`, for week-over-week deltas.
+    #[cfg(feature = "store")]
+    pub fn week_ago(
+        &self,
+        store: &Store,
+        now: &DateTime<Utc>,
+    ) -> Result<HashMap<String, UserSnapshot>, Error> {
+        let week_ago = *now - Duration::weeks(1);
+        let mut snapshots = HashMap::new();
+        for username in &self.usernames {
+            if let Some(snapshot) = store.snapshot_at(username, &week_ago)? {
+                snapshots.insert(username.clone(), snapshot);
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// Stores a snapshot of every fetched member, so later leaderboards have a baseline.
+    #[cfg(feature = "store")]
+    pub fn save_snapshots(&self, store: &mut Store, users: &[User]) -> Result<(), Error> {
+        for user in users {
+            store.save_snapshot(&UserSnapshot::new(user.clone()))?;
+        }
+        Ok(())
+    }
+}
+
+/// A structure that holds a row of a leaderboard.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    /// Position from 1. Members with equal values share a position.
+    pub position: usize,
+    pub username: String,
+    /// Overall rank, or rank in the language the leaderboard is ranked by.
+    pub rank: String,
+    pub value: i64,
+    /// Change of the value since the baseline, `None` without a baseline.
+    pub delta: Option<i64>,
+    /// Positions gained since the baseline, negative when the member dropped. `None`
+    /// without a baseline.
+    pub movement: Option<i64>,
+}
+
+/// A structure that holds a ranked leaderboard of a team.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Leaderboard {
+    pub team: String,
+    pub sort_by: SortBy,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// Returns users with their values, best first, and their positions.
+fn ranked<'a>(
+    users: impl Iterator<Item = &'a User>,
+    sort_by: &SortBy,
+) -> Vec<(usize, &'a User, i64)> {
+    let mut values: Vec<(&User, i64)> = users
+        .filter_map(|user| sort_by.value(user).map(|value| (user, value)))
+        .collect();
+    values.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.username.cmp(&b.0.username)));
+    let mut ranked: Vec<(usize, &User, i64)> = vec![];
+    for (n, (user, value)) in values.into_iter().enumerate() {
+        let position = match ranked.last() {
+            Some((position, _, previous)) if *previous == value => *position,
+            _ => n + 1,
+        };
+        ranked.push((position, user, value));
+    }
+    ranked
+}
+
+impl Leaderboard {
+    /// Returns a leaderboard of users. `baselines` are earlier snapshots of members by
+    /// username, e.g. from `Team::week_ago`; deltas are `None` for members without one.
+    pub fn new(
+        team: &str,
+        users: &[User],
+        sort_by: SortBy,
+        baselines: &HashMap<String, UserSnapshot>,
+    ) -> Self {
+        let previous: HashMap<&str, (usize, i64)> =
+            ranked(baselines.values().map(|snapshot| &snapshot.user), &sort_by)
+                .into_iter()
+                .map(|(position, user, value)| (user.username.as_str(), (position, value)))
+                .collect();
+        let entries = ranked(users.iter(), &sort_by)
+            .into_iter()
+            .map(|(position, user, value)| {
+                let previous = previous.get(user.username.as_str());
+                LeaderboardEntry {
+                    position,
+                    username: user.username.clone(),
+                    rank: sort_by.rank_name(user),
+                    value,
+                    delta: previous.map(|(_, previous_value)| value - previous_value),
+                    movement: previous
+                        .map(|(previous_position, _)| *previous_position as i64 - position as i64),
+                }
+            })
+            .collect();
+        Leaderboard {
+            team: team.to_string(),
+            sort_by,
+            entries,
+        }
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "{} leaderboard by {}",
+            self.team,
+            self.sort_by.label().to_lowercase()
+        )
+    }
+
+    /// Returns rows of cells shared by Markdown and HTML output.
+    fn cells(&self) -> Vec<[String; 5]> {
+        let signed = |value: Option<i64>| match value {
+            Some(value) if value > 0 => format!("+{}", value),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        };
+        self.entries
+            .iter()
+            .map(|entry| {
+                let movement = match entry.movement {
+                    Some(movement) if movement > 0 => format!(" ▲{}", movement),
+                    Some(movement) if movement < 0 => format!(" ▼{}", -movement),
+                    _ => String::new(),
+                };
+                [
+                    format!("{}{}", entry.position, movement),
+                    entry.username.clone(),
+                    entry.rank.clone(),
+                    entry.value.to_string(),
+                    signed(entry.delta),
+                ]
+            })
+            .collect()
+    }
+
+    /// Returns the leaderboard as a Markdown section with a table.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "## {}\n\n| # | User | Rank | {} | Week |\n|---|---|---|---:|---:|\n",
+            self.title(),
+            self.sort_by.label()
+        );
+        for cells in self.cells() {
+            let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+            let _ = writeln!(markdown, "| {} |", cells.join(" | "));
+        }
+        markdown
+    }
+
+    /// Returns the leaderboard as an HTML fragment with a heading and a table.
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<h2>{}</h2>\n<table>\n<thead><tr><th>#</th><th>User</th><th>Rank</th><th>{}</th>\
+             <th>Week</th></tr></thead>\n<tbody>\n",
+            escape_html(&self.title()),
+            escape_html(&self.sort_by.label())
+        );
+        for cells in self.cells() {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+                .collect();
+            let _ = writeln!(html, "<tr>{}</tr>", cells.concat());
+        }
+        html.push_str("</tbody>\n</table>\n");
+        html
+    }
+
+    /// Returns the leaderboard as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).context(JsonError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Leaderboard, SortBy, Team};
+    use crate::codewars::Codewars;
+    use crate::err::Error;
+    use crate::snapshot::UserSnapshot;
+    use codewars_mock::MockServerBuilder;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+
+    fn user(username: &str, honor: i64) -> Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/users/some_user.json");
+        let mut user: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        user["username"] = json!(username);
+        user["honor"] = json!(honor);
+        user
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let server = MockServerBuilder::new()
+            .user(user("alice", 500))
+            .user(user("bob", 700))
+            .user(user("carol", 500))
+            .start()
+            .unwrap();
+        let codewars =
+            Codewars::with_base_url("".to_string(), server.base_url()).with_concurrency(2);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backend.txt");
+        fs::write(&path, "# Backend team\nalice\nbob\n\ncarol\nnobody\n").unwrap();
+        let team = Team::from_file(&path).unwrap();
+        assert_eq!(team.name, "backend");
+
+        let (users, errors) = team.fetch(&codewars);
+        let usernames: Vec<&str> = users.iter().map(|u| u.username.as_str()).collect();
+        assert_eq!(usernames, vec!["alice", "bob", "carol"]);
+        assert!(
+            matches!(&errors[..], [(username, Error::UserNotFound { .. })] if username == "nobody")
+        );
+
+        let mut baselines = HashMap::new();
+        for (username, honor) in &[("alice", 450), ("bob", 690), ("carol", 480)] {
+            let mut baseline = users
+                .iter()
+                .find(|u| u.username == *username)
+                .unwrap()
+                .clone();
+            baseline.honor = Some(*honor);
+            baselines.insert(username.to_string(), UserSnapshot::new(baseline));
+        }
+        baselines.remove("carol");
+        let leaderboard = Leaderboard::new(&team.name, &users, SortBy::Honor, &baselines);
+        let positions: Vec<(usize, &str)> = leaderboard
+            .entries
+            .iter()
+            .map(|entry| (entry.position, entry.username.as_str()))
+            .collect();
+        assert_eq!(positions, vec![(1, "bob"), (2, "alice"), (2, "carol")]);
+        assert_eq!(leaderboard.entries[0].delta, Some(10));
+        assert_eq!(leaderboard.entries[1].delta, Some(50));
+        assert_eq!(leaderboard.entries[1].movement, Some(0));
+        assert_eq!(leaderboard.entries[2].delta, None);
+
+        let markdown = leaderboard.to_markdown();
+        assert!(markdown.starts_with("## backend leaderboard by honor\n"));
+        assert!(markdown.contains("\n| 1 | bob | 3 kyu | 700 | +10 |\n"));
+        assert!(markdown.contains("\n| 2 | carol | 3 kyu | 500 |  |\n"));
+        assert!(leaderboard
+            .to_html()
+            .contains("<tr><td>2</td><td>alice</td><td>3 kyu</td><td>500</td><td>+50</td></tr>"));
+        assert!(leaderboard.to_json().unwrap().contains("\"movement\": 0"));
+
+        let by_language = Leaderboard::new(
+            &team.name,
+            &users,
+            SortBy::Language("python".to_string()),
+            &HashMap::new(),
+        );
+        assert!(by_language.entries.is_empty());
+    }
+}
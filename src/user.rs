@@ -3,7 +3,7 @@ use serde_json::Value;
 use std::collections::HashMap;
 
 /// A structure that holds overall rank for a user.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct OverallRank {
     pub rank: i64,
     pub name: String,
@@ -31,7 +31,7 @@ impl Default for OverallRank {
 }
 
 /// A structure that holds overall ranks and ranks in various languages for a user.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Ranks {
     pub overall: OverallRank,
     pub languages: HashMap<String, OverallRank>,
@@ -55,7 +55,7 @@ impl Default for Ranks {
 }
 
 /// A structure that holds a code challenges solved by a user.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CodeChallenges {
     pub total_authored: u64,
     pub total_completed: u64,
@@ -79,7 +79,7 @@ impl Default for CodeChallenges {
 }
 
 /// A structure that holds and represent a single Codewars user.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct User {
     pub name: String,
     pub username: String,
@@ -134,8 +134,8 @@ impl User {
 
         // Extract Vectors
         let user_skills = response_json.get("skills").unwrap().as_array();
-        if user_skills.is_some() {
-            for skill in user_skills.unwrap() {
+        if let Some(user_skills) = user_skills {
+            for skill in user_skills {
                 self.skills.push(String::from(skill.as_str().unwrap()));
             }
         }
@@ -1,6 +1,9 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// A structure that holds a single completed challenge.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CompletedChallenge {
     pub id: String,
     pub name: String,
@@ -47,6 +50,13 @@ impl CompletedChallenge {
         self.completed_at = challenge_completed_at.to_string();
         // self.completed_languages.push(completed_languages);
     }
+
+    /// Returns `completed_at` as a time, or `None` if it is not a valid RFC 3339 time.
+    pub fn completed_at_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.completed_at)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
 }
 
 impl Default for CompletedChallenge {
@@ -56,7 +66,50 @@ impl Default for CompletedChallenge {
     }
 }
 
+/// A structure that holds a single page of challenges completed by a user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CompletedChallengesPage {
+    pub total_pages: u64,
+    pub total_items: u64,
+    pub data: Vec<CompletedChallenge>,
+}
+
+impl CompletedChallengesPage {
+    /// Returns a new instance of CompletedChallengesPage struct.
+    pub fn new() -> Self {
+        CompletedChallengesPage {
+            total_pages: 0,
+            total_items: 0,
+            data: vec![],
+        }
+    }
+
+    /// Extracts fields for a struct from supplied JSON and fill up struct fields with those values
+    pub fn from_json(&mut self, response_json: &Value) {
+        self.total_pages = response_json.get("totalPages").unwrap().as_u64().unwrap();
+        self.total_items = response_json
+            .get("totalItems")
+            .and_then(|total_items| total_items.as_u64())
+            .unwrap_or_default();
+
+        let data = response_json.get("data").unwrap().as_array().unwrap();
+        for d in data {
+            let mut completed_challenge = CompletedChallenge::new();
+            completed_challenge.from_json(d);
+            self.data.push(completed_challenge);
+        }
+    }
+}
+
+impl Default for CompletedChallengesPage {
+    /// Returns a new instance of CompletedChallengesPage struct with default values.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// A structure that holds a single authored challenge
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AuthoredChallenge {
     pub id: String,
     pub name: String,
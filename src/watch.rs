@@ -0,0 +1,500 @@
+//! Periodic polling of users that emits typed change events.
+//!
+//! The first poll of a user only records a baseline; events are emitted for changes
+//! found by later polls.
+
+use crate::codewars::Codewars;
+use crate::err::Error;
+use crate::snapshot::{UserDiff, UserSnapshot};
+use crate::user::OverallRank;
+use crate::user_challenges::{AuthoredChallenge, CompletedChallenge};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A change of a watched user.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Rank went up. `language` is `None` for the overall rank; `from` is `None` for a
+    /// language the user didn't have a rank in.
+    RankUp {
+        username: String,
+        language: Option<String>,
+        from: Option<OverallRank>,
+        to: OverallRank,
+    },
+    HonorChanged {
+        username: String,
+        from: Option<i64>,
+        to: Option<i64>,
+    },
+    KataCompleted {
+        username: String,
+        challenge: CompletedChallenge,
+    },
+    KataAuthored {
+        username: String,
+        challenge: AuthoredChallenge,
+    },
+    ClanChanged {
+        username: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// A user couldn't be polled. Only sent by [`Watcher::spawn`]; [`Watcher::poll`]
+    /// returns errors in [`PollOutcome::errors`].
+    PollFailed { username: String, message: String },
+}
+
+impl Event {
+    /// Returns username of the user the event is about.
+    pub fn username(&self) -> &str {
+        match self {
+            Event::RankUp { username, .. }
+            | Event::HonorChanged { username, .. }
+            | Event::KataCompleted { username, .. }
+            | Event::KataAuthored { username, .. }
+            | Event::ClanChanged { username, .. }
+            | Event::PollFailed { username, .. } => username,
+        }
+    }
+}
+
+/// A structure that holds events and errors of a single poll.
+#[derive(Debug, Default)]
+pub struct PollOutcome {
+    pub events: Vec<Event>,
+    /// Users that couldn't be polled, with the reason. They are retried on the next poll.
+    pub errors: Vec<(String, Error)>,
+}
+
+/// What is known about a watched user since the last poll.
+struct WatchState {
+    snapshot: UserSnapshot,
+    completed_ids: HashSet<String>,
+    authored_ids: HashSet<String>,
+}
+
+type Handler = Box<dyn FnMut(&Event) + Send>;
+
+/// Polls a set of users and emits events for their changes.
+pub struct Watcher {
+    codewars: Codewars,
+    usernames: Vec<String>,
+    interval: Duration,
+    states: HashMap<String, WatchState>,
+    handlers: Vec<Handler>,
+}
+
+impl Watcher {
+    /// Returns a new watcher that polls given users every `interval`.
+    pub fn new(codewars: Codewars, usernames: Vec<String>, interval: Duration) -> Self {
+        Watcher {
+            codewars,
+            usernames,
+            interval,
+            states: HashMap::new(),
+            handlers: vec![],
+        }
+    }
+
+    /// Registers a callback invoked for every event, in the order events are found.
+    pub fn on_event<F>(&mut self, handler: F)
+    where
+        F: FnMut(&Event) + Send + 'static,
+    {
+        self.handlers.push(Box::new(handler));
+    }
+
+    /// Polls every user once and returns events found since the previous poll.
+    pub fn poll(&mut self) -> PollOutcome {
+        let mut outcome = PollOutcome::default();
+        for username in self.usernames.clone() {
+            match self.poll_user(&username) {
+                Ok(events) => outcome.events.extend(events),
+                Err(e) => outcome.errors.push((username, e)),
+            }
+        }
+        for event in &outcome.events {
+            for handler in self.handlers.iter_mut() {
+                handler(event);
+            }
+        }
+        outcome
+    }
+
+    /// Polls users forever, sleeping `interval` between polls. Events are delivered to
+    /// registered callbacks.
+    pub fn run(mut self) {
+        loop {
+            self.poll();
+            thread::sleep(self.interval);
+        }
+    }
+
+    /// Polls users on a background thread and sends events to the returned channel,
+    /// followed by an `Event::PollFailed` for every user that couldn't be polled.
+    ///
+    /// A dropped receiver is only noticed when the thread sends to it, so the thread
+    /// keeps polling until the next event or error after the receiver is dropped.
+    pub fn spawn(mut self) -> (Receiver<Event>, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel();
+        let handle = thread::spawn(move || loop {
+            let outcome = self.poll();
+            let failures = outcome
+                .errors
+                .into_iter()
+                .map(|(username, error)| Event::PollFailed {
+                    username,
+                    message: error.to_string(),
+                });
+            for event in outcome.events.into_iter().chain(failures) {
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+            thread::sleep(self.interval);
+        });
+        (receiver, handle)
+    }
+
+    fn poll_user(&mut self, username: &str) -> Result<Vec<Event>, Error> {
+        // Cached responses would hide changes
+        self.codewars.invalidate_user(username);
+
+        let user = self.codewars.get_user(username.to_string())?;
+        let first_page = self
+            .codewars
+            .get_completed_challenges_page(username.to_string(), 0)?;
+        let total_pages = first_page.total_pages;
+        let mut completed = first_page.data;
+        let snapshot = UserSnapshot::new(user);
+
+        let previous = match self.states.remove(username) {
+            Some(previous) => previous,
+            None => {
+                let authored_ids = if snapshot.user.code_challenges.total_authored > 0 {
+                    self.authored(username)?
+                        .into_iter()
+                        .map(|challenge| challenge.id)
+                        .collect()
+                } else {
+                    HashSet::new()
+                };
+                self.states.insert(
+                    username.to_string(),
+                    WatchState {
+                        snapshot,
+                        completed_ids: completed.into_iter().map(|c| c.id).collect(),
+                        authored_ids,
+                    },
+                );
+                return Ok(vec![]);
+            }
+        };
+
+        // New completions can span pages, fetch pages until one holds a kata seen before
+        let mut page = 1;
+        while page < total_pages
+            && !completed
+                .iter()
+                .any(|challenge| previous.completed_ids.contains(&challenge.id))
+        {
+            match self
+                .codewars
+                .get_completed_challenges_page(username.to_string(), page)
+            {
+                Ok(next_page) => completed.extend(next_page.data),
+                Err(e) => {
+                    self.states.insert(username.to_string(), previous);
+                    return Err(e);
+                }
+            }
+            page += 1;
+        }
+
+        let mut events = vec![];
+        let diff = UserDiff::between(&previous.snapshot, &snapshot);
+        for change in diff.overall_rank_up.into_iter().chain(diff.rank_ups) {
+            events.push(Event::RankUp {
+                username: username.to_string(),
+                language: change.language,
+                from: change.from,
+                to: change.to,
+            });
+        }
+        if diff.honor_delta != 0 {
+            events.push(Event::HonorChanged {
+                username: username.to_string(),
+                from: previous.snapshot.user.honor,
+                to: snapshot.user.honor,
+            });
+        }
+        if let Some(clan_change) = diff.clan_change {
+            events.push(Event::ClanChanged {
+                username: username.to_string(),
+                from: clan_change.from,
+                to: clan_change.to,
+            });
+        }
+
+        let mut completed_ids = previous.completed_ids.clone();
+        // Pages are ordered most recent first, events are emitted oldest first
+        for challenge in completed.into_iter().rev() {
+            if completed_ids.insert(challenge.id.clone()) {
+                events.push(Event::KataCompleted {
+                    username: username.to_string(),
+                    challenge,
+                });
+            }
+        }
+
+        let mut authored_ids = previous.authored_ids.clone();
+        if diff.authored_delta > 0 {
+            // Keep the previous state untouched if authored challenges can't be retrieved,
+            // so all changes of this poll are reported by the next one
+            let authored = match self.authored(username) {
+                Ok(authored) => authored,
+                Err(e) => {
+                    self.states.insert(username.to_string(), previous);
+                    return Err(e);
+                }
+            };
+            for challenge in authored {
+                if authored_ids.insert(challenge.id.clone()) {
+                    events.push(Event::KataAuthored {
+                        username: username.to_string(),
+                        challenge,
+                    });
+                }
+            }
+        }
+
+        self.states.insert(
+            username.to_string(),
+            WatchState {
+                snapshot,
+                completed_ids,
+                authored_ids,
+            },
+        );
+        Ok(events)
+    }
+
+    fn authored(&self, username: &str) -> Result<Vec<AuthoredChallenge>, Error> {
+        self.codewars.get_authored_challenges(username.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Event, Watcher};
+    use crate::codewars::Codewars;
+    use codewars_mock::{Fault, MockServerBuilder};
+    use serde_json::{json, Value};
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn fixture(path: &str) -> Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(path);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_poll_emits_events() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let server = MockServerBuilder::from_dir(&data_dir)
+            .unwrap()
+            .start()
+            .unwrap();
+        let codewars = Codewars::with_base_url("".to_string(), server.base_url());
+        let mut watcher = Watcher::new(
+            codewars,
+            vec!["some_user".to_string()],
+            Duration::from_secs(60),
+        );
+        let seen = Arc::new(Mutex::new(vec![]));
+        let handler_seen = Arc::clone(&seen);
+        watcher.on_event(move |event| handler_seen.lock().unwrap().push(event.clone()));
+
+        assert!(watcher.poll().events.is_empty());
+
+        let mut user = fixture("users/some_user.json");
+        user["honor"] = json!(600);
+        user["ranks"]["languages"]["rust"] =
+            json!({"rank": -8, "name": "8 kyu", "color": "white", "score": 2});
+        server.set_user(user);
+        let mut completed = fixture("completed/some_user.json")
+            .as_array()
+            .unwrap()
+            .clone();
+        completed.insert(
+            0,
+            json!({
+                "id": "54da5a58ea159efa38000836",
+                "name": "Find the odd int",
+                "slug": "find-the-odd-int",
+                "completedAt": "2017-04-07T10:00:00Z",
+                "completedLanguages": ["rust"]
+            }),
+        );
+        server.set_completed_challenges("some_user", completed);
+
+        let outcome = watcher.poll();
+        assert!(outcome.errors.is_empty());
+        assert_eq!(outcome.events.len(), 3);
+        assert!(matches!(
+            &outcome.events[0],
+            Event::RankUp { language: Some(language), from: None, .. } if language == "rust"
+        ));
+        assert!(matches!(
+            outcome.events[1],
+            Event::HonorChanged {
+                from: Some(544),
+                to: Some(600),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &outcome.events[2],
+            Event::KataCompleted { challenge, .. } if challenge.slug == "find-the-odd-int"
+        ));
+        assert_eq!(seen.lock().unwrap().len(), 3);
+
+        assert!(watcher.poll().events.is_empty());
+    }
+
+    #[test]
+    fn test_poll_fetches_pages_until_a_seen_kata() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let server = MockServerBuilder::from_dir(&data_dir)
+            .unwrap()
+            .page_size(2)
+            .start()
+            .unwrap();
+        let codewars = Codewars::with_base_url("".to_string(), server.base_url());
+        let mut watcher = Watcher::new(
+            codewars,
+            vec!["some_user".to_string()],
+            Duration::from_secs(60),
+        );
+        assert!(watcher.poll().events.is_empty());
+
+        // Three new completions push the newest kata seen before to the second page
+        let mut completed = fixture("completed/some_user.json")
+            .as_array()
+            .unwrap()
+            .clone();
+        for id in &["b", "c", "d"] {
+            completed.insert(
+                0,
+                json!({
+                    "id": id,
+                    "name": format!("Kata {}", id),
+                    "slug": format!("kata-{}", id),
+                    "completedAt": "2017-04-07T10:00:00Z",
+                    "completedLanguages": ["rust"]
+                }),
+            );
+        }
+        server.set_completed_challenges("some_user", completed);
+
+        let outcome = watcher.poll();
+        assert!(outcome.errors.is_empty());
+        let slugs: Vec<&str> = outcome
+            .events
+            .iter()
+            .map(|event| match event {
+                Event::KataCompleted { challenge, .. } => challenge.slug.as_str(),
+                _ => panic!("unexpected event {:?}", event),
+            })
+            .collect();
+        assert_eq!(slugs, vec!["kata-b", "kata-c", "kata-d"]);
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.ends_with("completed?page=1")));
+    }
+
+    #[test]
+    fn test_poll_keeps_changes_when_authored_fails() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let server = MockServerBuilder::from_dir(&data_dir)
+            .unwrap()
+            .start()
+            .unwrap();
+        let codewars = Codewars::with_base_url("".to_string(), server.base_url());
+        let mut watcher = Watcher::new(
+            codewars,
+            vec!["some_user".to_string()],
+            Duration::from_secs(60),
+        );
+        assert!(watcher.poll().events.is_empty());
+
+        let mut user = fixture("users/some_user.json");
+        user["honor"] = json!(600);
+        user["codeChallenges"]["totalAuthored"] = json!(4);
+        server.set_user(user);
+        let mut authored = fixture("authored/some_user.json")
+            .as_array()
+            .unwrap()
+            .clone();
+        let mut new_kata = authored[0].clone();
+        new_kata["id"] = json!("new");
+        authored.push(new_kata);
+        server.set_authored_challenges("some_user", authored);
+        let mut completed = fixture("completed/some_user.json")
+            .as_array()
+            .unwrap()
+            .clone();
+        let mut new_completion = completed[0].clone();
+        new_completion["id"] = json!("new");
+        completed.insert(0, new_completion);
+        server.set_completed_challenges("some_user", completed);
+        server.add_fault(
+            "/users/some_user/code-challenges/authored",
+            Fault::InternalServerError,
+            Some(1),
+        );
+
+        let failed = watcher.poll();
+        assert_eq!(failed.errors.len(), 1);
+        assert!(failed.events.is_empty());
+
+        let retried = watcher.poll();
+        assert!(retried.errors.is_empty());
+        assert_eq!(retried.events.len(), 3);
+        assert!(matches!(retried.events[0], Event::HonorChanged { .. }));
+        assert!(matches!(
+            &retried.events[1],
+            Event::KataCompleted { challenge, .. } if challenge.id == "new"
+        ));
+        assert!(matches!(
+            &retried.events[2],
+            Event::KataAuthored { challenge, .. } if challenge.id == "new"
+        ));
+    }
+
+    #[test]
+    fn test_spawn_sends_poll_errors() {
+        let server = MockServerBuilder::new().start().unwrap();
+        let codewars = Codewars::with_base_url("".to_string(), server.base_url());
+        let watcher = Watcher::new(
+            codewars,
+            vec!["nobody".to_string()],
+            Duration::from_secs(60),
+        );
+        let (events, _) = watcher.spawn();
+        let event = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            event,
+            Event::PollFailed { username, message } if username == "nobody" && message.contains("not found")
+        ));
+    }
+}
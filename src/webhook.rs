@@ -0,0 +1,360 @@
+//! Receiving Codewars webhooks.
+//!
+//! Codewars sends the configured secret in `X-Webhook-Secret` header and the kind of
+//! event in `X-Webhook-Event` header (`user` or `code_challenge`). The body is JSON
+//! with an `action` field and the object the action is about. [`Webhook`] verifies the
+//! secret, parses the body into a [`WebhookEvent`] and dispatches it to handlers. With
+//! `webhook-server` feature, [`WebhookServer`] receives webhooks over HTTP.
+
+use crate::err::{Error, JsonError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::ResultExt;
+
+/// Header that carries the webhook secret.
+pub const SECRET_HEADER: &str = "X-Webhook-Secret";
+
+/// Header that carries the kind of event.
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// A structure that holds the user part of a user webhook.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UserPayload {
+    pub id: String,
+    pub rank: Option<i64>,
+    pub rank_was: Option<i64>,
+    pub honor: Option<i64>,
+    pub honor_was: Option<i64>,
+}
+
+/// A structure that holds the code challenge part of a code challenge webhook.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CodeChallengePayload {
+    pub id: String,
+    pub created_by_id: Option<String>,
+    pub approved_by_id: Option<String>,
+}
+
+/// A parsed webhook.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookEvent {
+    RankEarned(UserPayload),
+    HonorChanged(UserPayload),
+    CodeChallengeCreated(CodeChallengePayload),
+    CodeChallengeApproved(CodeChallengePayload),
+    CodeChallengeVoted(CodeChallengePayload),
+    /// An action this crate doesn't know about yet, with the whole body.
+    Unknown {
+        event: Option<String>,
+        action: String,
+        payload: Value,
+    },
+}
+
+impl WebhookEvent {
+    /// Parses a webhook body. `event` is the value of `X-Webhook-Event` header, if any.
+    pub fn parse(event: Option<&str>, body: &[u8]) -> Result<Self, Error> {
+        let payload: Value = serde_json::from_slice(body).context(JsonError)?;
+        let action = payload
+            .get("action")
+            .and_then(|action| action.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let user = || -> Result<UserPayload, Error> {
+            serde_json::from_value(payload.get("user").cloned().unwrap_or(Value::Null))
+                .context(JsonError)
+        };
+        let code_challenge = || -> Result<CodeChallengePayload, Error> {
+            serde_json::from_value(
+                payload
+                    .get("code_challenge")
+                    .cloned()
+                    .unwrap_or(Value::Null),
+            )
+            .context(JsonError)
+        };
+
+        let is_user = payload.get("user").is_some() || event == Some("user");
+        let is_code_challenge =
+            payload.get("code_challenge").is_some() || event == Some("code_challenge");
+        match action.as_str() {
+            "rank_earned" if is_user => Ok(WebhookEvent::RankEarned(user()?)),
+            "honor_changed" if is_user => Ok(WebhookEvent::HonorChanged(user()?)),
+            "created" if is_code_challenge => {
+                Ok(WebhookEvent::CodeChallengeCreated(code_challenge()?))
+            }
+            "approved" if is_code_challenge => {
+                Ok(WebhookEvent::CodeChallengeApproved(code_challenge()?))
+            }
+            "voted" if is_code_challenge => Ok(WebhookEvent::CodeChallengeVoted(code_challenge()?)),
+            _ => Ok(WebhookEvent::Unknown {
+                event: event.map(|event| event.to_string()),
+                action,
+                payload,
+            }),
+        }
+    }
+}
+
+/// Returns true if the secret received in `X-Webhook-Secret` header matches the
+/// expected one. The comparison takes the same time wherever the secrets differ.
+pub fn verify_secret(expected: &str, received: Option<&str>) -> bool {
+    let received = match received {
+        Some(received) => received.as_bytes(),
+        None => return false,
+    };
+    let expected = expected.as_bytes();
+    if expected.len() != received.len() {
+        return false;
+    }
+    expected
+        .iter()
+        .zip(received)
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+type Handler = Box<dyn Fn(&WebhookEvent) + Send + Sync>;
+
+/// Verifies, parses and dispatches webhooks to registered handlers.
+pub struct Webhook {
+    secret: String,
+    handlers: Vec<Handler>,
+}
+
+impl Webhook {
+    /// Returns a new instance of Webhook struct that accepts webhooks with given secret.
+    pub fn new(secret: String) -> Self {
+        Webhook {
+            secret,
+            handlers: vec![],
+        }
+    }
+
+    /// Registers a handler invoked for every accepted webhook.
+    pub fn on_event<F>(&mut self, handler: F)
+    where
+        F: Fn(&WebhookEvent) + Send + Sync + 'static,
+    {
+        self.handlers.push(Box::new(handler));
+    }
+
+    /// Handles a single webhook given its `X-Webhook-Secret` and `X-Webhook-Event` header
+    /// values and body. Returns the dispatched event.
+    pub fn handle(
+        &self,
+        secret: Option<&str>,
+        event: Option<&str>,
+        body: &[u8],
+    ) -> Result<WebhookEvent, Error> {
+        if !verify_secret(&self.secret, secret) {
+            return Err(Error::InvalidWebhookSecret);
+        }
+        let event = WebhookEvent::parse(event, body)?;
+        for handler in &self.handlers {
+            handler(&event);
+        }
+        Ok(event)
+    }
+}
+
+#[cfg(feature = "webhook-server")]
+pub use self::server::{WebhookServer, MAX_BODY};
+
+#[cfg(feature = "webhook-server")]
+mod server {
+
+    use super::{Webhook, EVENT_HEADER, SECRET_HEADER};
+    use crate::err::Error;
+    use std::io::{self, Read};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+    use tiny_http::{Request, Response, Server};
+
+    /// Largest body accepted, in bytes. Codewars webhooks are well below it.
+    pub const MAX_BODY: u64 = 64 * 1024;
+
+    /// A small HTTP server that passes every POST request to a [`Webhook`]. It responds
+    /// with `204` to accepted webhooks, `401` to a wrong secret, `400` to a body that
+    /// can't be parsed and `413` to a body over [`MAX_BODY`]. The server stops when this
+    /// handle is dropped.
+    pub struct WebhookServer {
+        server: Arc<Server>,
+        addr: SocketAddr,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl WebhookServer {
+        /// Starts the server on given address, e.g. `0.0.0.0:8080`.
+        pub fn start(addr: &str, webhook: Webhook) -> io::Result<Self> {
+            let server = Server::http(addr).map_err(io::Error::other)?;
+            let addr = server
+                .server_addr()
+                .to_ip()
+                .ok_or_else(|| io::Error::other("Server is not listening on an IP address"))?;
+            let server = Arc::new(server);
+            let thread_server = Arc::clone(&server);
+            let handle = thread::spawn(move || {
+                for request in thread_server.incoming_requests() {
+                    handle_request(&webhook, request);
+                }
+            });
+            Ok(WebhookServer {
+                server,
+                addr,
+                handle: Some(handle),
+            })
+        }
+
+        /// Returns the address server is listening on.
+        pub fn addr(&self) -> SocketAddr {
+            self.addr
+        }
+
+        /// Blocks current thread until the server stops.
+        pub fn wait(mut self) {
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    impl Drop for WebhookServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    fn header(request: &Request, name: &'static str) -> Option<String> {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.to_string())
+    }
+
+    fn handle_request(webhook: &Webhook, mut request: Request) {
+        if request.method() != &tiny_http::Method::Post {
+            let _ = request.respond(Response::empty(405));
+            return;
+        }
+        let secret = header(&request, SECRET_HEADER);
+        let event = header(&request, EVENT_HEADER);
+        if request.body_length().unwrap_or(0) as u64 > MAX_BODY {
+            let _ = request.respond(Response::empty(413));
+            return;
+        }
+        let mut body = vec![];
+        if request
+            .as_reader()
+            .take(MAX_BODY + 1)
+            .read_to_end(&mut body)
+            .is_err()
+        {
+            let _ = request.respond(Response::empty(400));
+            return;
+        }
+        if body.len() as u64 > MAX_BODY {
+            let _ = request.respond(Response::empty(413));
+            return;
+        }
+        let status = match webhook.handle(secret.as_deref(), event.as_deref(), &body) {
+            Ok(_) => 204,
+            Err(Error::InvalidWebhookSecret) => 401,
+            Err(_) => 400,
+        };
+        let _ = request.respond(Response::empty(status));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{verify_secret, Webhook, WebhookEvent};
+    use crate::err::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const HONOR_CHANGED: &str = r#"{
+        "action": "honor_changed",
+        "user": {"id": "5a1b5d5e7a9d4c0001a1b2c3", "honor": 1010, "honor_was": 1000}
+    }"#;
+
+    #[test]
+    fn test_verify_secret() {
+        assert!(verify_secret("secret", Some("secret")));
+        assert!(!verify_secret("secret", Some("secreT")));
+        assert!(!verify_secret("secret", Some("secret2")));
+        assert!(!verify_secret("secret", None));
+    }
+
+    #[test]
+    fn test_handle_dispatches_parsed_events() {
+        let mut webhook = Webhook::new("secret".to_string());
+        let count = Arc::new(AtomicUsize::new(0));
+        let handler_count = Arc::clone(&count);
+        webhook.on_event(move |_| {
+            handler_count.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let event = webhook
+            .handle(Some("secret"), Some("user"), HONOR_CHANGED.as_bytes())
+            .unwrap();
+        match event {
+            WebhookEvent::HonorChanged(user) => {
+                assert_eq!(user.honor, Some(1010));
+                assert_eq!(user.honor_was, Some(1000));
+            }
+            other => panic!("Unexpected event {:?}", other),
+        }
+
+        let created = webhook
+            .handle(
+                Some("secret"),
+                Some("code_challenge"),
+                br#"{"action": "created", "code_challenge": {"id": "abc", "created_by_id": "def"}}"#,
+            )
+            .unwrap();
+        assert!(matches!(created, WebhookEvent::CodeChallengeCreated(_)));
+
+        let unknown = webhook
+            .handle(Some("secret"), None, br#"{"action": "something_new"}"#)
+            .unwrap();
+        assert!(matches!(unknown, WebhookEvent::Unknown { .. }));
+
+        let rejected = webhook.handle(Some("wrong"), Some("user"), HONOR_CHANGED.as_bytes());
+        assert!(matches!(rejected, Err(Error::InvalidWebhookSecret)));
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[cfg(feature = "webhook-server")]
+    #[test]
+    fn test_server() {
+        use super::{WebhookServer, EVENT_HEADER, MAX_BODY, SECRET_HEADER};
+
+        let server =
+            WebhookServer::start("127.0.0.1:0", Webhook::new("secret".to_string())).unwrap();
+        let url = format!("http://{}/", server.addr());
+        let client = reqwest::blocking::Client::new();
+        let post = |secret: &str, body: String| {
+            client
+                .post(&url)
+                .header(SECRET_HEADER, secret)
+                .header(EVENT_HEADER, "user")
+                .body(body)
+                .send()
+                .unwrap()
+                .status()
+                .as_u16()
+        };
+        assert_eq!(post("secret", HONOR_CHANGED.to_string()), 204);
+        assert_eq!(post("wrong", HONOR_CHANGED.to_string()), 401);
+        let too_large = " ".repeat(MAX_BODY as usize + 1) + HONOR_CHANGED;
+        assert_eq!(post("secret", too_large), 413);
+    }
+}